@@ -1,35 +1,91 @@
 use std::str::FromStr;
 
-use futures::future::join_all;
+use futures::future::{join_all, try_join};
 use solana_program::native_token::LAMPORTS_PER_SOL;
 use solana_sdk::pubkey::Pubkey;
 use spl_token::id as spl_token_id;
 
 use crate::{
+    balances::lst::get_lst_rates,
     client::{
-        types::AccountData, GetTokenAccountsByOwnerConfig, GetTokenAccountsByOwnerFilter,
-        SolanaMirrorClient,
+        types::{AccountData, MintInfo, TokenExtension},
+        GetTokenAccountsByOwnerConfig, GetTokenAccountsByOwnerFilter, SolanaMirrorClient,
     },
     coingecko::get_coingecko_id,
-    price::get_price,
+    price::{lookup_prices, PriceQuote},
     types::FormattedAmount,
     utils::{fetch_image, fetch_metadata},
     Error, SOL_ADDRESS, SOL_IMAGE,
 };
 
-use types::ParsedAta;
+use spam::{get_mint_authorities, is_spam, MintAuthorities, SpamSignals};
+use types::{ParsedAta, TokenExtensions};
 
+pub mod spam;
 pub mod types;
 
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
 /// Fetches the token accounts associated with the given address and parses them.
 pub async fn get_parsed_accounts(
     client: &SolanaMirrorClient,
     address: &Pubkey,
 ) -> Result<Vec<ParsedAta>, Error> {
     let accounts = get_accounts(client, address).await?;
-    let parse_futures = accounts
+
+    let mints: Vec<Pubkey> = accounts
+        .iter()
+        .map(|account| parse_mint(&account.account.data.parsed.info.mint))
+        .collect::<Result<_, _>>()?;
+    // The authorities only feed the spam score, a failure to fetch them shouldn't fail the balances
+    let authorities = get_mint_authorities(client, &mints)
+        .await
+        .unwrap_or_default();
+
+    let all_lst_rates = get_lst_rates(client).await;
+    let lst_rates: Vec<Option<f64>> = mints
         .iter()
-        .map(|account| parse_account(client, account));
+        .map(|mint| all_lst_rates.get(mint).copied())
+        .collect();
+
+    // Every price is looked up in a single batch, LSTs only need the SOL one
+    let sol = Pubkey::from_str(SOL_ADDRESS).unwrap();
+    let mut mints_to_price: Vec<(Pubkey, u8)> = vec![(sol, 9)];
+    for ((account, mint), lst_rate) in accounts.iter().zip(&mints).zip(&lst_rates) {
+        if lst_rate.is_none() {
+            mints_to_price.push((
+                *mint,
+                account.account.data.parsed.info.token_amount.decimals,
+            ));
+        }
+    }
+    let prices = lookup_prices(client, &mints_to_price).await;
+
+    let parse_futures =
+        accounts
+            .iter()
+            .zip(&mints)
+            .zip(lst_rates)
+            .map(|((account, mint), lst_rate)| {
+                let authorities = authorities.get(mint).copied().unwrap_or_default();
+                // LSTs are priced from their redemption value instead of a quote, which drifts for illiquid ones
+                let (quote, price_failed) = match lst_rate {
+                    Some(rate) => (
+                        prices.quotes.get(&sol).map(|sol_quote| PriceQuote {
+                            price: sol_quote.price * rate,
+                            source: "stake-pool".to_string(),
+                            fetched_at: sol_quote.fetched_at,
+                            liquidity: None,
+                        }),
+                        prices.failed.contains(&sol),
+                    ),
+                    None => (
+                        prices.quotes.get(mint).cloned(),
+                        prices.failed.contains(mint),
+                    ),
+                };
+                parse_account(client, account, quote, price_failed, lst_rate, authorities)
+            });
 
     let parsed_results = join_all(parse_futures).await;
 
@@ -41,13 +97,17 @@ pub async fn get_parsed_accounts(
         }
     }
 
-    parsed_accounts.push(get_solana(client, address).await);
+    parsed_accounts.push(get_solana(client, address, prices.quotes.get(&sol).cloned()).await);
     Ok(parsed_accounts)
 }
 
 /// Fetches the SOL account associated with the given address.
-async fn get_solana(client: &SolanaMirrorClient, pubkey: &Pubkey) -> ParsedAta {
-    let price = get_price(client, Pubkey::from_str(SOL_ADDRESS).unwrap(), Some(9)).await;
+async fn get_solana(
+    client: &SolanaMirrorClient,
+    pubkey: &Pubkey,
+    quote: Option<PriceQuote>,
+) -> ParsedAta {
+    let price = quote.as_ref().map(|quote| quote.price);
 
     let amount = client.get_balance(pubkey, None).await.unwrap_or(0);
 
@@ -66,19 +126,46 @@ async fn get_solana(client: &SolanaMirrorClient, pubkey: &Pubkey) -> ParsedAta {
             amount: amount.to_string(),
             formatted,
         },
+        value_usd: price.map(|price| price * formatted),
+        price_quote: quote,
+        market_value_usd: None,
+        exit_liquidity: None,
+        weight: None,
+        program: "native".to_string(),
+        spam: false,
+        is_lst: false,
+        underlying_sol: None,
+        extensions: None,
     }
 }
 
-/// Fetches the token accounts associated with the given address.
-async fn get_accounts(
+/// Fetches the token accounts associated with the given address,
+/// under both the SPL Token and the Token-2022 programs.
+pub async fn get_accounts(
+    client: &SolanaMirrorClient,
+    pubkey: &Pubkey,
+) -> Result<Vec<AccountData>, Error> {
+    let (spl_token_accounts, token_2022_accounts) = try_join(
+        get_program_accounts(client, pubkey, &spl_token_id().to_string()),
+        get_program_accounts(client, pubkey, TOKEN_2022_PROGRAM_ID),
+    )
+    .await?;
+
+    let mut accounts = spl_token_accounts;
+    accounts.extend(token_2022_accounts);
+    Ok(accounts)
+}
+
+async fn get_program_accounts(
     client: &SolanaMirrorClient,
     pubkey: &Pubkey,
+    program_id: &str,
 ) -> Result<Vec<AccountData>, Error> {
     let accounts = client
         .get_token_accounts_by_owner(
             pubkey,
             Some(GetTokenAccountsByOwnerFilter {
-                program_id: spl_token_id().to_string(),
+                program_id: program_id.to_string(),
             }),
             Some(GetTokenAccountsByOwnerConfig {
                 commitment: None,
@@ -92,28 +179,50 @@ async fn get_accounts(
     Ok(accounts.result.value)
 }
 
-/// Parses the given account.
+/// Parses the given account. `price_failed` tells a token without a quote because
+/// the lookup failed apart from one no source knows, only the latter counts as spam
 async fn parse_account(
     client: &SolanaMirrorClient,
     account: &AccountData,
+    quote: Option<PriceQuote>,
+    price_failed: bool,
+    lst_rate: Option<f64>,
+    authorities: MintAuthorities,
 ) -> Result<ParsedAta, Error> {
     let data = &account.account.data;
     let info = &data.parsed.info;
     let mint = &info.mint;
 
-    let metadata = fetch_metadata(client, mint).await;
+    let program = data.program.clone();
+
+    // Token-2022 mints carry their extensions, and possibly their metadata, in the mint itself
+    let mint_info = if program == "spl-token-2022" {
+        client.get_mint(&parse_mint(mint)?).await.ok()
+    } else {
+        None
+    };
+
+    let metadata = fetch_metadata(client, mint, mint_info.as_ref()).await;
 
     let ata = &account.pubkey;
     let decimals = info.token_amount.decimals;
     let amount = info.token_amount.amount.parse::<u64>().unwrap();
     let formatted = info.token_amount.ui_amount;
-
-    let mint_pubkey = Pubkey::from_str(mint).unwrap();
-    let price = get_price(client, mint_pubkey, Some(decimals)).await;
+    let price = quote.as_ref().map(|quote| quote.price);
 
     let coingecko_id = get_coingecko_id(mint).await;
     let image = fetch_image(&metadata).await;
 
+    let spam = is_spam(&SpamSignals {
+        mint,
+        name: &metadata.name,
+        symbol: &metadata.symbol,
+        uri: &metadata.uri,
+        unpriced: price.is_none() && !price_failed,
+        has_coingecko_id: coingecko_id.is_some(),
+        authorities,
+    });
+
     Ok(ParsedAta {
         mint: mint.to_string(),
         ata: ata.to_string(),
@@ -127,5 +236,44 @@ async fn parse_account(
             amount: amount.to_string(),
             formatted,
         },
+        value_usd: price.map(|price| price * formatted),
+        price_quote: quote,
+        market_value_usd: None,
+        exit_liquidity: None,
+        weight: None,
+        program,
+        spam,
+        is_lst: lst_rate.is_some(),
+        underlying_sol: lst_rate.map(|rate| formatted * rate),
+        extensions: mint_info.as_ref().map(summarize_extensions),
     })
 }
+
+/// Mints come from the RPC response, an invalid one means the response is malformed
+fn parse_mint(mint: &str) -> Result<Pubkey, Error> {
+    Pubkey::from_str(mint).map_err(|e| Error::parse_error().with_source(e).with_address(mint))
+}
+
+/// Picks the mint extensions that are relevant to the holder
+fn summarize_extensions(mint_info: &MintInfo) -> TokenExtensions {
+    let mut extensions = TokenExtensions::default();
+
+    for extension in &mint_info.extensions {
+        match extension {
+            TokenExtension::TransferFeeConfig(config) => {
+                extensions.transfer_fee_bps =
+                    Some(config.newer_transfer_fee.transfer_fee_basis_points);
+                extensions.transfer_fee_max = Some(config.newer_transfer_fee.maximum_fee);
+            }
+            TokenExtension::InterestBearingConfig(config) => {
+                extensions.interest_rate_bps = Some(config.current_rate);
+            }
+            TokenExtension::MetadataPointer(pointer) => {
+                extensions.metadata_pointer = pointer.metadata_address.clone();
+            }
+            _ => {}
+        }
+    }
+
+    extensions
+}
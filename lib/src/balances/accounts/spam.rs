@@ -0,0 +1,239 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{client::SolanaMirrorClient, utils::read_u32, Error, SOL_ADDRESS, USDC_ADDRESS};
+
+const USDT_ADDRESS: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+/// Symbols that scam tokens copy to pass as the real thing, with their real mint
+const IMPERSONATED_SYMBOLS: [(&str, &str); 4] = [
+    ("USDC", USDC_ADDRESS),
+    ("USDT", USDT_ADDRESS),
+    ("SOL", SOL_ADDRESS),
+    ("WSOL", SOL_ADDRESS),
+];
+
+/// Words airdropped tokens use to lure holders into a drainer site
+const SUSPICIOUS_WORDS: [&str; 8] = [
+    "claim", "airdrop", "reward", "bonus", "voucher", "visit", "gift", "free",
+];
+
+const URL_MARKERS: [&str; 6] = ["http", "www.", ".com", ".io", ".xyz", ".net"];
+
+const MINT_AUTHORITY_OFFSET: usize = 0;
+const FREEZE_AUTHORITY_OFFSET: usize = 46;
+
+/// Score from which a token is flagged as spam
+const SPAM_THRESHOLD: u32 = 4;
+
+/// Mints always flagged as spam, from the comma separated `SPAM_DENYLIST` env var
+static DENYLIST: Lazy<HashSet<String>> = Lazy::new(|| read_mint_list("SPAM_DENYLIST"));
+
+/// Mints never flagged as spam, from the comma separated `SPAM_ALLOWLIST` env var
+static ALLOWLIST: Lazy<HashSet<String>> = Lazy::new(|| read_mint_list("SPAM_ALLOWLIST"));
+
+/// Signals gathered while parsing a token, used to tell spam apart
+pub struct SpamSignals<'a> {
+    pub mint: &'a str,
+    pub name: &'a str,
+    pub symbol: &'a str,
+    pub uri: &'a str,
+    /// No source knows a price for the token, a failed lookup doesn't count
+    pub unpriced: bool,
+    pub has_coingecko_id: bool,
+    pub authorities: MintAuthorities,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MintAuthorities {
+    /// More tokens can still be minted
+    pub mint: bool,
+    /// Holders can get their accounts frozen
+    pub freeze: bool,
+}
+
+/// Scores the signals of a token, the allow and deny lists take precedence over the score.
+/// A token is only flagged if its name, symbol or uri host is suspicious
+pub fn is_spam(signals: &SpamSignals) -> bool {
+    if ALLOWLIST.contains(signals.mint) {
+        return false;
+    }
+    if DENYLIST.contains(signals.mint) {
+        return true;
+    }
+
+    // Plenty of legit tokens are unpriced and unlisted, only what the token says about
+    // itself tells spam apart, the rest of the signals just add weight to it
+    let suspicious = is_suspicious_uri(signals.uri)
+        || is_suspicious_label(signals.name)
+        || is_suspicious_label(signals.symbol);
+    let impersonating = is_impersonating(signals.mint, signals.name, signals.symbol);
+    if !suspicious && !impersonating {
+        return false;
+    }
+
+    let mut score = 0;
+
+    // Tokens without any route to USDC can't be sold, which is what most spam looks like
+    if signals.unpriced {
+        score += 2;
+    }
+    if !signals.has_coingecko_id {
+        score += 1;
+    }
+    if suspicious {
+        score += 3;
+    }
+    if impersonating {
+        score += 5;
+    }
+    if signals.authorities.mint {
+        score += 1;
+    }
+    if signals.authorities.freeze {
+        score += 1;
+    }
+
+    score >= SPAM_THRESHOLD
+}
+
+/// Reads the mint and freeze authorities of the given mints in a single batch.
+/// Token-2022 mints start with the same layout, so both programs are covered
+pub async fn get_mint_authorities(
+    client: &SolanaMirrorClient,
+    mints: &[Pubkey],
+) -> Result<HashMap<Pubkey, MintAuthorities>, Error> {
+    let accounts = client.get_multiple_accounts(mints).await?;
+
+    let authorities = accounts
+        .into_iter()
+        .flatten()
+        .map(|account| {
+            let authorities = MintAuthorities {
+                mint: read_u32(&account.data, MINT_AUTHORITY_OFFSET).is_some_and(|tag| tag == 1),
+                freeze: read_u32(&account.data, FREEZE_AUTHORITY_OFFSET)
+                    .is_some_and(|tag| tag == 1),
+            };
+            (account.pubkey, authorities)
+        })
+        .collect();
+
+    Ok(authorities)
+}
+
+fn read_mint_list(var: &str) -> HashSet<String> {
+    env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|mint| !mint.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_suspicious_text(text: &str) -> bool {
+    let text = text.to_lowercase();
+    SUSPICIOUS_WORDS.iter().any(|word| text.contains(word))
+}
+
+/// Only the host is checked, paths are often hashes that can contain any word
+fn is_suspicious_uri(uri: &str) -> bool {
+    reqwest::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(is_suspicious_text))
+        .unwrap_or(false)
+}
+
+/// Legit tokens don't put links or calls to action in their name or symbol, drainers do
+fn is_suspicious_label(label: &str) -> bool {
+    let label = label.to_lowercase();
+    URL_MARKERS.iter().any(|marker| label.contains(marker)) || is_suspicious_text(&label)
+}
+
+fn is_impersonating(mint: &str, name: &str, symbol: &str) -> bool {
+    IMPERSONATED_SYMBOLS
+        .iter()
+        .any(|(impersonated, real_mint)| {
+            mint != *real_mint
+                && (symbol.trim().eq_ignore_ascii_case(impersonated)
+                    || name.trim().eq_ignore_ascii_case(impersonated))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn signals<'a>(name: &'a str, symbol: &'a str, uri: &'a str) -> SpamSignals<'a> {
+        SpamSignals {
+            mint: MINT,
+            name,
+            symbol,
+            uri,
+            unpriced: true,
+            has_coingecko_id: false,
+            authorities: MintAuthorities {
+                mint: true,
+                freeze: true,
+            },
+        }
+    }
+
+    #[test]
+    fn keeps_unlisted_tokens_without_suspicious_content() {
+        assert!(!is_spam(&signals(
+            "Some Token",
+            "SOME",
+            "https://arweave.net/abc"
+        )));
+    }
+
+    #[test]
+    fn ignores_suspicious_words_in_uri_paths() {
+        assert!(!is_spam(&signals(
+            "Some Token",
+            "SOME",
+            "https://arweave.net/x7Fr3eGiftQ2freeCLaim9vIsItrEwArd1"
+        )));
+        assert!(!is_spam(&signals(
+            "Some Token",
+            "SOME",
+            "https://example.com/free/gift.json"
+        )));
+        assert!(is_spam(&signals(
+            "Some Token",
+            "SOME",
+            "https://claim-airdrop.xyz/token.json"
+        )));
+    }
+
+    #[test]
+    fn flags_suspicious_content_of_unlisted_tokens() {
+        assert!(is_spam(&signals(
+            "Claim at drop.xyz",
+            "CLAIM",
+            "https://arweave.net/abc"
+        )));
+        assert!(is_spam(&signals(
+            "USD Coin",
+            "USDC",
+            "https://arweave.net/abc"
+        )));
+    }
+
+    #[test]
+    fn keeps_priced_tokens_with_a_suspicious_word() {
+        let signals = SpamSignals {
+            unpriced: false,
+            has_coingecko_id: true,
+            authorities: MintAuthorities::default(),
+            ..signals("Reward Token", "RWD", "https://arweave.net/abc")
+        };
+        assert!(!is_spam(&signals));
+    }
+}
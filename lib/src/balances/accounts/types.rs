@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::types::FormattedAmount;
+use crate::{
+    price::{PriceLiquidity, PriceQuote},
+    types::FormattedAmount,
+};
 
 #[derive(Default, Debug, Serialize)]
 pub struct ParsedAta {
@@ -14,6 +17,46 @@ pub struct ParsedAta {
     pub image: String,
     pub price: Option<f64>,
     pub balance: FormattedAmount,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+    /// Where the price comes from and when it was looked up
+    #[serde(rename = "priceQuote", skip_serializing_if = "Option::is_none")]
+    pub price_quote: Option<PriceQuote>,
+    /// Value at the market price, only set when `valueUsd` is the liquidation value
+    #[serde(rename = "marketValueUsd", skip_serializing_if = "Option::is_none")]
+    pub market_value_usd: Option<f64>,
+    /// Depth behind the quote of the whole balance, only set for the liquidation valuation
+    #[serde(rename = "exitLiquidity", skip_serializing_if = "Option::is_none")]
+    pub exit_liquidity: Option<PriceLiquidity>,
+    /// Share of the wallet gross value (`totals.gross`), between 0 and 1
+    pub weight: Option<f64>,
+    /// Owner program of the token, either `spl-token` or `spl-token-2022`
+    pub program: String,
+    /// Whether the token looks like airdropped spam or a scam
+    pub spam: bool,
+    /// Whether the token is a liquid staking token backed by staked SOL
+    #[serde(rename = "isLst")]
+    pub is_lst: bool,
+    /// SOL the balance redeems for at the stake pool exchange rate, only set for LSTs
+    #[serde(rename = "underlyingSol", skip_serializing_if = "Option::is_none")]
+    pub underlying_sol: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<TokenExtensions>,
+}
+
+/// Summary of the Token-2022 extensions that affect how a holding is valued
+#[derive(Default, Debug, Serialize)]
+pub struct TokenExtensions {
+    /// Fee charged on every transfer, from the newest fee schedule
+    #[serde(rename = "transferFeeBps", skip_serializing_if = "Option::is_none")]
+    pub transfer_fee_bps: Option<u16>,
+    #[serde(rename = "transferFeeMax", skip_serializing_if = "Option::is_none")]
+    pub transfer_fee_max: Option<u64>,
+    /// Interest rate in basis points, already reflected in the formatted balance
+    #[serde(rename = "interestRateBps", skip_serializing_if = "Option::is_none")]
+    pub interest_rate_bps: Option<i16>,
+    #[serde(rename = "metadataPointer", skip_serializing_if = "Option::is_none")]
+    pub metadata_pointer: Option<String>,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
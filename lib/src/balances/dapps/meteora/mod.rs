@@ -0,0 +1,233 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use futures::future::try_join;
+
+use crate::{
+    balances::accounts::types::ParsedAta,
+    client::{types::KeyedAccount, ProgramAccountsFilter, SolanaMirrorClient},
+    utils::mul_shift_64,
+    Error,
+};
+
+use super::{format_fee_rate, DappAdapter, PositionSnapshot, PositionToken, PriceRange};
+use types::{Bin, LbPair, PositionV2, MAX_BIN_PER_ARRAY};
+
+pub mod types;
+
+const METEORA_DLMM_PROGRAM_ID: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9t2S6MCtu";
+
+const POSITION_OWNER_OFFSET: usize = 40;
+
+/// Meteora DLMM positions, plain accounts owned by the wallet
+pub struct MeteoraDlmmAdapter;
+
+#[rocket::async_trait]
+impl DappAdapter for MeteoraDlmmAdapter {
+    fn id(&self) -> &'static str {
+        "meteora-dlmm"
+    }
+
+    fn name(&self) -> &'static str {
+        "Meteora DLMM"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        Pubkey::from_str(METEORA_DLMM_PROGRAM_ID).unwrap()
+    }
+
+    async fn discover(
+        &self,
+        client: &SolanaMirrorClient,
+        owner: &Pubkey,
+    ) -> Result<Vec<KeyedAccount>, Error> {
+        client
+            .get_program_accounts(
+                &self.program_id(),
+                vec![
+                    ProgramAccountsFilter::DataSize(PositionV2::LEN as u64),
+                    ProgramAccountsFilter::memcmp(POSITION_OWNER_OFFSET, owner),
+                ],
+                None,
+            )
+            .await
+    }
+
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        _token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error> {
+        let position_data =
+            PositionV2::from_bytes(&position.data).ok_or_else(|| too_short(&position.pubkey))?;
+
+        let pair_id = position_data.lb_pair;
+        let pair_accounts = client.get_multiple_accounts(&[pair_id]).await?;
+        let pair = pair_accounts
+            .first()
+            .and_then(|pair| pair.as_ref())
+            .and_then(|pair| LbPair::from_bytes(&pair.data))
+            .ok_or_else(|| too_short(&pair_id))?;
+
+        let bins = get_bins(client, &pair_id, &position_data).await?;
+
+        let (decimals_x, decimals_y) = try_join(
+            get_mint_decimals(client, &pair.token_x_mint),
+            get_mint_decimals(client, &pair.token_y_mint),
+        )
+        .await?;
+
+        let mut amount_x = 0.0;
+        let mut amount_y = 0.0;
+        let mut fees_x: u64 = 0;
+        let mut fees_y: u64 = 0;
+
+        for (i, bin_id) in (position_data.lower_bin_id..=position_data.upper_bin_id).enumerate() {
+            let share = position_data.liquidity_shares[i];
+            let fee_info = &position_data.fee_infos[i];
+            fees_x = fees_x.saturating_add(fee_info.fee_x_pending);
+            fees_y = fees_y.saturating_add(fee_info.fee_y_pending);
+
+            let Some(bin) = bins.get(&bin_id) else {
+                continue;
+            };
+            if share == 0 || bin.liquidity_supply == 0 {
+                continue;
+            }
+
+            let ratio = share as f64 / bin.liquidity_supply as f64;
+            amount_x += (bin.amount_x as f64 * ratio).floor();
+            amount_y += (bin.amount_y as f64 * ratio).floor();
+
+            // Fees accrued since the position was last updated, shares are Q64 scaled
+            let shares = share >> 64;
+            fees_x = fees_x.saturating_add(mul_shift_64(
+                shares,
+                bin.fee_amount_x_per_token_stored
+                    .saturating_sub(fee_info.fee_x_per_token_complete),
+            ));
+            fees_y = fees_y.saturating_add(mul_shift_64(
+                shares,
+                bin.fee_amount_y_per_token_stored
+                    .saturating_sub(fee_info.fee_y_per_token_complete),
+            ));
+        }
+
+        let fees = [
+            (pair.token_x_mint, decimals_x, fees_x),
+            (pair.token_y_mint, decimals_y, fees_y),
+        ]
+        .into_iter()
+        .filter(|(_, _, amount)| *amount > 0)
+        .map(|(mint, decimals, amount)| PositionToken {
+            mint,
+            decimals,
+            amount: amount as f64,
+        })
+        .collect();
+
+        // Base fee in 1e-9, the dynamic part depends on recent volatility and is left out
+        let base_fee = pair.base_factor as u64
+            * pair.bin_step as u64
+            * 10
+            * 10_u64.pow(pair.base_fee_power_factor as u32);
+
+        Ok(PositionSnapshot {
+            mint: None,
+            pool_id: pair_id,
+            token_a: PositionToken {
+                mint: pair.token_x_mint,
+                decimals: decimals_x,
+                amount: amount_x,
+            },
+            token_b: PositionToken {
+                mint: pair.token_y_mint,
+                decimals: decimals_y,
+                amount: amount_y,
+            },
+            fee_tier: format_fee_rate((base_fee / 1_000) as u32),
+            // The upper bound is where the last bin of the position ends
+            price_range: Some(PriceRange {
+                lower: get_price_from_bin_id(position_data.lower_bin_id, pair.bin_step),
+                upper: get_price_from_bin_id(position_data.upper_bin_id + 1, pair.bin_step),
+                current: get_price_from_bin_id(pair.active_id, pair.bin_step),
+            }),
+            fees,
+            rewards: Vec::new(),
+        })
+    }
+}
+
+/// Price of token X in token Y at the given bin, in base units
+pub fn get_price_from_bin_id(bin_id: i32, bin_step: u16) -> f64 {
+    (1.0 + bin_step as f64 / 10_000.0).powi(bin_id)
+}
+
+pub fn get_bin_array_index(bin_id: i32) -> i64 {
+    (bin_id as i64).div_euclid(MAX_BIN_PER_ARRAY as i64)
+}
+
+pub fn get_bin_array_address(lb_pair: &Pubkey, index: i64) -> Pubkey {
+    let program_id = Pubkey::from_str(METEORA_DLMM_PROGRAM_ID).unwrap();
+    let seeds = &[
+        b"bin_array".as_ref(),
+        lb_pair.as_ref(),
+        &index.to_le_bytes(),
+    ];
+    let (bin_array_address, _bump) = Pubkey::find_program_address(seeds, &program_id);
+    bin_array_address
+}
+
+/// Loads the bins covered by the position, keyed by bin id. A position spans
+/// at most 70 bins, so two bin arrays at most
+async fn get_bins(
+    client: &SolanaMirrorClient,
+    lb_pair: &Pubkey,
+    position: &PositionV2,
+) -> Result<HashMap<i32, Bin>, Error> {
+    let lower_index = get_bin_array_index(position.lower_bin_id);
+    let upper_index = get_bin_array_index(position.upper_bin_id);
+    let indexes: Vec<i64> = (lower_index..=upper_index).collect();
+    let addresses: Vec<Pubkey> = indexes
+        .iter()
+        .map(|index| get_bin_array_address(lb_pair, *index))
+        .collect();
+
+    let bin_arrays = client.get_multiple_accounts(&addresses).await?;
+
+    let mut bins = HashMap::new();
+    for (index, bin_array) in indexes.into_iter().zip(bin_arrays) {
+        // Bin arrays are only created once liquidity is added to them
+        let Some(bin_array) = bin_array else {
+            continue;
+        };
+
+        let first_bin_id = index * MAX_BIN_PER_ARRAY as i64;
+        for bin_id in position.lower_bin_id..=position.upper_bin_id {
+            let offset = bin_id as i64 - first_bin_id;
+            if !(0..MAX_BIN_PER_ARRAY as i64).contains(&offset) {
+                continue;
+            }
+
+            let bin = Bin::from_bin_array(&bin_array.data, offset as usize)
+                .ok_or_else(|| too_short(&bin_array.pubkey))?;
+            bins.insert(bin_id, bin);
+        }
+    }
+
+    Ok(bins)
+}
+
+/// Pairs don't store the decimals of their mints
+async fn get_mint_decimals(client: &SolanaMirrorClient, mint: &Pubkey) -> Result<u8, Error> {
+    let decimals = client.get_decimals(mint, None).await?;
+    Ok(decimals.result.value.decimals)
+}
+
+fn too_short(address: &Pubkey) -> Error {
+    Error::parse_error()
+        .with_source("account not found or too short")
+        .with_address(address)
+}
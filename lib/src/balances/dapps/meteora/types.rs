@@ -0,0 +1,123 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::utils::{read_i32, read_pubkey, read_u128, read_u16, read_u64};
+
+/// Bins a position or a bin array can span
+pub const MAX_BIN_PER_ARRAY: usize = 70;
+
+/// Accounts of the DLMM program are zero-copy, with arrays too long for serde,
+/// so they are read field by field. Only the fields we need are decoded
+#[derive(Debug, Default)]
+pub struct PositionV2 {
+    pub lb_pair: Pubkey,
+    pub owner: Pubkey,
+    /// Q64 shares of each bin from `lower_bin_id` up
+    pub liquidity_shares: Vec<u128>,
+    pub fee_infos: Vec<FeeInfo>,
+    pub lower_bin_id: i32,
+    pub upper_bin_id: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct FeeInfo {
+    pub fee_x_per_token_complete: u128,
+    pub fee_y_per_token_complete: u128,
+    pub fee_x_pending: u64,
+    pub fee_y_pending: u64,
+}
+
+impl PositionV2 {
+    pub const LEN: usize = 8120;
+
+    const LIQUIDITY_SHARES_OFFSET: usize = 72;
+    /// Reward infos (48 bytes per bin) sit between the shares and the fee infos
+    const FEE_INFOS_OFFSET: usize = 4552;
+    const FEE_INFO_LEN: usize = 48;
+    const LOWER_BIN_ID_OFFSET: usize = 7912;
+
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let liquidity_shares = (0..MAX_BIN_PER_ARRAY)
+            .map(|i| read_u128(data, Self::LIQUIDITY_SHARES_OFFSET + i * 16))
+            .collect::<Option<Vec<_>>>()?;
+
+        let fee_infos = (0..MAX_BIN_PER_ARRAY)
+            .map(|i| {
+                let offset = Self::FEE_INFOS_OFFSET + i * Self::FEE_INFO_LEN;
+                Some(FeeInfo {
+                    fee_x_per_token_complete: read_u128(data, offset)?,
+                    fee_y_per_token_complete: read_u128(data, offset + 16)?,
+                    fee_x_pending: read_u64(data, offset + 32)?,
+                    fee_y_pending: read_u64(data, offset + 40)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let lower_bin_id = read_i32(data, Self::LOWER_BIN_ID_OFFSET)?;
+        let upper_bin_id = read_i32(data, Self::LOWER_BIN_ID_OFFSET + 4)?;
+        let width = upper_bin_id as i64 - lower_bin_id as i64;
+        if !(0..MAX_BIN_PER_ARRAY as i64).contains(&width) {
+            return None;
+        }
+
+        Some(Self {
+            lb_pair: read_pubkey(data, 8)?,
+            owner: read_pubkey(data, 40)?,
+            liquidity_shares,
+            fee_infos,
+            lower_bin_id,
+            upper_bin_id,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LbPair {
+    pub base_factor: u16,
+    pub base_fee_power_factor: u8,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+}
+
+impl LbPair {
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            base_factor: read_u16(data, 8)?,
+            base_fee_power_factor: *data.get(34)?,
+            active_id: read_i32(data, 76)?,
+            bin_step: read_u16(data, 80)?,
+            token_x_mint: read_pubkey(data, 88)?,
+            token_y_mint: read_pubkey(data, 120)?,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Bin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub liquidity_supply: u128,
+    pub fee_amount_x_per_token_stored: u128,
+    pub fee_amount_y_per_token_stored: u128,
+}
+
+impl Bin {
+    /// Discriminator, index, version, padding and pair come before the bins
+    pub const BINS_OFFSET: usize = 56;
+    pub const LEN: usize = 144;
+
+    /// Reads the bin at `index` inside a bin array
+    pub fn from_bin_array(data: &[u8], index: usize) -> Option<Self> {
+        let offset = Self::BINS_OFFSET + index * Self::LEN;
+        Some(Self {
+            amount_x: read_u64(data, offset)?,
+            amount_y: read_u64(data, offset + 8)?,
+            // price (u128) is skipped
+            liquidity_supply: read_u128(data, offset + 32)?,
+            // reward_per_token_stored ([u128; 2]) is skipped
+            fee_amount_x_per_token_stored: read_u128(data, offset + 80)?,
+            fee_amount_y_per_token_stored: read_u128(data, offset + 96)?,
+        })
+    }
+}
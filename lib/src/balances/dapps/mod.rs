@@ -1,2 +1,244 @@
+use std::collections::HashMap;
+
+use futures::future::join_all;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::accounts::types::ParsedAta,
+    client::{types::KeyedAccount, SolanaMirrorClient},
+    price::{get_prices_with_decimals, PriceQuote},
+    types::{FormattedAmount, FormattedAmountWithPrice},
+    utils::{fetch_image, fetch_metadata},
+    Error,
+};
+
+use types::{ParsedPosition, ProtocolInfo, TokenPosition};
+
+pub mod meteora;
+pub mod orca;
 pub mod raydium;
+pub mod raydium_amm;
+pub mod registry;
 pub mod types;
+
+/// A protocol whose positions are held by the wallet, either as tokens
+/// (see `detect`) or as accounts owned by the wallet (see `discover`)
+#[rocket::async_trait]
+pub trait DappAdapter: Send + Sync {
+    /// Short name the positions are keyed by, eg. `raydium`
+    fn id(&self) -> &'static str;
+
+    /// Shown when the position has no token metadata to take the name from
+    fn name(&self) -> &'static str;
+
+    fn program_id(&self) -> Pubkey;
+
+    /// Derives the account that would hold the position if the token belonged to this protocol.
+    /// Returns `None` for tokens that can't be one of its positions
+    fn detect(&self, _account: &ParsedAta) -> Option<Pubkey> {
+        None
+    }
+
+    /// Looks up positions that aren't represented by a token, by their owner
+    async fn discover(
+        &self,
+        _client: &SolanaMirrorClient,
+        _owner: &Pubkey,
+    ) -> Result<Vec<KeyedAccount>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// Whether the derived account really is a position of this protocol
+    fn matches(&self, account: &KeyedAccount) -> bool {
+        account.owner == self.program_id().to_string()
+    }
+
+    /// Decodes the position and the pool it belongs to. `token` is the account
+    /// the position was detected through, `None` for discovered positions
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error>;
+
+    /// Prices the tokens of the position
+    async fn value(
+        &self,
+        client: &SolanaMirrorClient,
+        snapshot: PositionSnapshot,
+    ) -> ParsedPosition {
+        let mut parsed_position = parse_position(client, snapshot).await;
+        parsed_position.protocol.id = self.id().to_string();
+        if parsed_position.protocol.name.is_empty() {
+            parsed_position.protocol.name = self.name().to_string();
+        }
+        parsed_position
+    }
+}
+
+/// What a protocol reports about a position, before any pricing
+pub struct PositionSnapshot {
+    /// Mint of the token that represents the position, if any
+    pub mint: Option<String>,
+    pub pool_id: Pubkey,
+    pub token_a: PositionToken,
+    pub token_b: PositionToken,
+    pub fee_tier: String,
+    /// Range of the position in base units, for protocols where liquidity is concentrated
+    pub price_range: Option<PriceRange>,
+    /// Swap fees earned and not collected yet
+    pub fees: Vec<PositionToken>,
+    /// Farm rewards earned and not collected yet
+    pub rewards: Vec<PositionToken>,
+}
+
+/// Prices of token A in token B. Adapters report them in base units,
+/// `parse_position` adjusts them for decimals before they're returned
+pub struct PriceRange {
+    pub lower: f64,
+    pub upper: f64,
+    pub current: f64,
+}
+
+/// One side of a liquidity position, with the amount in base units
+pub struct PositionToken {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub amount: f64,
+}
+
+/// Position NFTs are minted with no decimals and a supply of one
+pub fn is_nft(account: &ParsedAta) -> bool {
+    account.decimals == 0 && account.balance.amount == "1"
+}
+
+/// Formats a fee rate given in hundredths of a basis point as a percentage, eg. `0.25%`
+pub fn format_fee_rate(fee_rate: u32) -> String {
+    format!("{}%", fee_rate as f64 / 10_000.0)
+}
+
+/// Builds the response for a liquidity position, pricing both of its tokens.
+/// The protocol info is taken from the metadata of the position token, if there's one
+pub async fn parse_position(
+    client: &SolanaMirrorClient,
+    snapshot: PositionSnapshot,
+) -> ParsedPosition {
+    let (metadata_protocol, image_protocol) = match &snapshot.mint {
+        Some(mint) => {
+            let metadata = fetch_metadata(client, mint, None).await;
+            let image = fetch_image(&metadata).await;
+            (metadata, image)
+        }
+        None => Default::default(),
+    };
+
+    // Human prices move by the difference in decimals, eg. lamports per USDC base unit
+    let decimals_adjustment =
+        10_f64.powi(snapshot.token_a.decimals as i32 - snapshot.token_b.decimals as i32);
+    let price_range = snapshot.price_range.map(|range| PriceRange {
+        lower: range.lower * decimals_adjustment,
+        upper: range.upper * decimals_adjustment,
+        current: range.current * decimals_adjustment,
+    });
+
+    let mints: Vec<(Pubkey, u8)> = [&snapshot.token_a, &snapshot.token_b]
+        .into_iter()
+        .chain(&snapshot.fees)
+        .chain(&snapshot.rewards)
+        .map(|token| (token.mint, token.decimals))
+        .collect();
+    let prices = get_prices_with_decimals(client, &mints).await;
+
+    let (token_a, price_a) = parse_token_position(client, snapshot.token_a, &prices).await;
+    let (token_b, price_b) = parse_token_position(client, snapshot.token_b, &prices).await;
+
+    let unclaimed_fees = join_all(
+        snapshot
+            .fees
+            .into_iter()
+            .map(|token| parse_token_position(client, token, &prices)),
+    )
+    .await;
+    let unclaimed_rewards = join_all(
+        snapshot
+            .rewards
+            .into_iter()
+            .map(|token| parse_token_position(client, token, &prices)),
+    )
+    .await;
+
+    // Everything the holder would get by closing the position, skipping tokens without a price
+    let values: Vec<f64> = [(&token_a, price_a), (&token_b, price_b)]
+        .into_iter()
+        .chain(unclaimed_fees.iter().map(|(token, price)| (token, *price)))
+        .chain(
+            unclaimed_rewards
+                .iter()
+                .map(|(token, price)| (token, *price)),
+        )
+        .filter_map(|(token, price)| price.map(|price| token.amount.amount.formatted * price))
+        .collect();
+
+    let total_value_usd = if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum())
+    };
+
+    ParsedPosition {
+        total_value_usd,
+        weight: None,
+        protocol: ProtocolInfo {
+            id: String::new(),
+            name: metadata_protocol.name,
+            symbol: metadata_protocol.symbol,
+            image: image_protocol,
+            pool_id: snapshot.pool_id.to_string(),
+        },
+        token_a,
+        token_b,
+        fee_tier: snapshot.fee_tier,
+        price_lower: price_range.as_ref().map(|range| range.lower),
+        price_upper: price_range.as_ref().map(|range| range.upper),
+        current_price: price_range.as_ref().map(|range| range.current),
+        in_range: price_range
+            .as_ref()
+            .map(|range| range.lower <= range.current && range.current < range.upper),
+        unclaimed_fees: unclaimed_fees.into_iter().map(|(token, _)| token).collect(),
+        unclaimed_rewards: unclaimed_rewards
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect(),
+    }
+}
+
+async fn parse_token_position(
+    client: &SolanaMirrorClient,
+    token: PositionToken,
+    prices: &HashMap<Pubkey, PriceQuote>,
+) -> (TokenPosition, Option<f64>) {
+    let metadata = fetch_metadata(client, &token.mint.to_string(), None).await;
+    let image = fetch_image(&metadata).await;
+    let quote = prices.get(&token.mint).cloned();
+    let price = quote.as_ref().map(|quote| quote.price);
+
+    let formatted = token.amount / (10_f64.powi(token.decimals as i32));
+
+    let token_position = TokenPosition {
+        mint: token.mint.to_string(),
+        name: metadata.name,
+        symbol: metadata.symbol,
+        image,
+        amount: FormattedAmountWithPrice {
+            amount: FormattedAmount {
+                amount: token.amount.to_string(),
+                formatted,
+            },
+            price: price.unwrap_or_default(),
+        },
+        price_quote: quote,
+    };
+
+    (token_position, price)
+}
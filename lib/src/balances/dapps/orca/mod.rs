@@ -0,0 +1,129 @@
+use serde::de::DeserializeOwned;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use futures::future::try_join;
+
+use crate::{
+    balances::accounts::types::ParsedAta,
+    client::{types::KeyedAccount, GetAccountDataConfig, SolanaMirrorClient},
+    utils::{
+        calculate_concentrated_liquidity_amounts, get_price_from_tick,
+        get_sqrt_price_from_sqrt_price_x64,
+    },
+    Error,
+};
+
+use super::{format_fee_rate, is_nft, DappAdapter, PositionSnapshot, PositionToken, PriceRange};
+use types::{Position, Whirlpool};
+
+pub mod types;
+
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+/// Orca Whirlpool positions
+pub struct OrcaAdapter;
+
+#[rocket::async_trait]
+impl DappAdapter for OrcaAdapter {
+    fn id(&self) -> &'static str {
+        "orca"
+    }
+
+    fn name(&self) -> &'static str {
+        "Orca Whirlpools"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        Pubkey::from_str(ORCA_WHIRLPOOL_PROGRAM_ID).unwrap()
+    }
+
+    fn detect(&self, account: &ParsedAta) -> Option<Pubkey> {
+        if !is_nft(account) {
+            return None;
+        }
+        get_position_address(&account.mint).ok()
+    }
+
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error> {
+        let position_data: Position = decode_data(&position.data, &position.pubkey)?;
+        let pool_id = position_data.whirlpool;
+        let pool = get_whirlpool_data(client, &pool_id).await?;
+
+        let (decimals_a, decimals_b) = try_join(
+            get_mint_decimals(client, &pool.token_mint_a),
+            get_mint_decimals(client, &pool.token_mint_b),
+        )
+        .await?;
+
+        let (amount_a, amount_b) = calculate_concentrated_liquidity_amounts(
+            position_data.liquidity,
+            position_data.tick_lower_index,
+            position_data.tick_upper_index,
+            pool.sqrt_price,
+        );
+
+        Ok(PositionSnapshot {
+            mint: token.map(|token| token.mint.clone()),
+            pool_id,
+            token_a: PositionToken {
+                mint: pool.token_mint_a,
+                decimals: decimals_a,
+                amount: amount_a,
+            },
+            token_b: PositionToken {
+                mint: pool.token_mint_b,
+                decimals: decimals_b,
+                amount: amount_b,
+            },
+            fee_tier: format_fee_rate(pool.fee_rate as u32),
+            price_range: Some(PriceRange {
+                lower: get_price_from_tick(position_data.tick_lower_index),
+                upper: get_price_from_tick(position_data.tick_upper_index),
+                current: get_sqrt_price_from_sqrt_price_x64(pool.sqrt_price).powi(2),
+            }),
+            fees: Vec::new(),
+            rewards: Vec::new(),
+        })
+    }
+}
+
+pub fn get_position_address(nft_mint: &str) -> Result<Pubkey, Error> {
+    let nft_mint_pubkey = Pubkey::from_str(nft_mint).map_err(|_| Error::InvalidAddress)?;
+    let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_PROGRAM_ID).unwrap();
+    let seeds = &[b"position", nft_mint_pubkey.as_ref()];
+    let (position_address, _bump) = Pubkey::find_program_address(seeds, &program_id);
+    Ok(position_address)
+}
+
+async fn get_whirlpool_data(
+    client: &SolanaMirrorClient,
+    pool_id: &Pubkey,
+) -> Result<Whirlpool, Error> {
+    let encoded_pool = client
+        .get_account_info(
+            pool_id,
+            Some(GetAccountDataConfig {
+                commitment: None,
+                encoding: Some("base64".to_string()),
+            }),
+        )
+        .await?;
+    decode_data(&encoded_pool, pool_id)
+}
+
+/// Whirlpools don't store the decimals of their mints
+async fn get_mint_decimals(client: &SolanaMirrorClient, mint: &Pubkey) -> Result<u8, Error> {
+    let decimals = client.get_decimals(mint, None).await?;
+    Ok(decimals.result.value.decimals)
+}
+
+fn decode_data<T: DeserializeOwned>(data: &[u8], address: &Pubkey) -> Result<T, Error> {
+    bincode::deserialize(data)
+        .map_err(|e| Error::parse_error().with_source(e).with_address(address))
+}
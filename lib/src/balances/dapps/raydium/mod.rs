@@ -1,135 +1,320 @@
+use futures::future::try_join_all;
 use serde::de::DeserializeOwned;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{
-    balances::dapps::types::{ProtocolInfo, TokenPosition},
-    client::{GetAccountDataConfig, SolanaMirrorClient},
-    price::get_price,
-    types::{FormattedAmount, FormattedAmountWithPrice},
-    utils::{calculate_concentrated_liquidity_amounts, fetch_image, fetch_metadata},
+    balances::accounts::types::ParsedAta,
+    client::{types::KeyedAccount, GetAccountDataConfig, SolanaMirrorClient},
+    utils::{
+        calculate_concentrated_liquidity_amounts, get_price_from_tick,
+        get_sqrt_price_from_sqrt_price_x64, mul_shift_64,
+    },
     Error,
 };
 
-use super::types::ParsedPosition;
-use types::{Pool, Position};
+use super::{format_fee_rate, is_nft, DappAdapter, PositionSnapshot, PositionToken, PriceRange};
+use types::{AmmConfig, Pool, Position, TickState};
 
 pub mod types;
 
 const RAYDIUM_CL_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
 
-pub async fn get_raydium_position(
-    client: &SolanaMirrorClient,
-    mint_protocol: &str,
-) -> Result<ParsedPosition, Error> {
-    let position_address = get_position_address(mint_protocol).unwrap();
-    let position = get_position_data(client, &position_address).await?;
-    let pool_id = position.pool_id;
-    let pool = get_pool_data(client, &pool_id).await?;
-
-    let (amount_a, amount_b) = calculate_concentrated_liquidity_amounts(
-        position.liquidity,
-        position.tick_lower,
-        position.tick_upper,
-        pool.sqrt_price_x64,
-    );
+/// Ticks in a tick array, each `tick_spacing` apart
+const TICK_ARRAY_SIZE: i32 = 60;
+/// Discriminator, pool id and start index come before the ticks
+const TICK_ARRAY_TICKS_OFFSET: usize = 8 + 32 + 4;
+const TICK_STATE_LEN: usize = 168;
 
-    let metadata_protocol = fetch_metadata(client, mint_protocol).await;
-    let image_protocol = fetch_image(&metadata_protocol).await;
+/// Raydium concentrated liquidity positions
+pub struct RaydiumAdapter;
 
-    let mint_a = pool.mint_a;
-    let mint_b = pool.mint_b;
+#[rocket::async_trait]
+impl DappAdapter for RaydiumAdapter {
+    fn id(&self) -> &'static str {
+        "raydium"
+    }
 
-    let metadata_token_a = fetch_metadata(client, &mint_a.to_string()).await;
-    let metadata_token_b = fetch_metadata(client, &mint_b.to_string()).await;
-    let image_a = fetch_image(&metadata_token_a).await;
-    let image_b = fetch_image(&metadata_token_b).await;
+    fn name(&self) -> &'static str {
+        "Raydium Concentrated Liquidity"
+    }
 
-    let decimals_a = pool.mint_decimals_a;
-    let decimals_b = pool.mint_decimals_b;
+    fn program_id(&self) -> Pubkey {
+        Pubkey::from_str(RAYDIUM_CL_PROGRAM_ID).unwrap()
+    }
 
-    let formatted_amount_a = amount_a / (10_f64.powi(decimals_a as i32));
-    let formatted_amount_b = amount_b / (10_f64.powi(decimals_b as i32));
+    fn detect(&self, account: &ParsedAta) -> Option<Pubkey> {
+        if !is_nft(account) {
+            return None;
+        }
+        get_position_address(&account.mint).ok()
+    }
 
-    let price_a = get_price(client, mint_a, Some(decimals_a)).await;
-    let price_b = get_price(client, mint_b, Some(decimals_b)).await;
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error> {
+        let position_data: Position = decode_data(&position.data, &position.pubkey)?;
+        let pool_id = position_data.pool_id;
+        let pool = get_pool_data(client, &pool_id).await?;
+        let amm_config = get_amm_config_data(client, &pool.amm_config).await?;
 
-    let total_value_usd = match (price_a, price_b) {
-        (Some(price_a), Some(price_b)) => {
-            Some((formatted_amount_a * price_a) + (formatted_amount_b * price_b))
-        }
-        (Some(price_a), None) => Some(formatted_amount_a * price_a),
-        (None, Some(price_b)) => Some(formatted_amount_b * price_b),
-        (None, None) => None,
-    };
+        let (amount_a, amount_b) = calculate_concentrated_liquidity_amounts(
+            position_data.liquidity,
+            position_data.tick_lower,
+            position_data.tick_upper,
+            pool.sqrt_price_x64,
+        );
+
+        let (tick_lower, tick_upper) =
+            get_tick_states(client, &pool_id, &pool, &position_data).await?;
+        let (fees_a, fees_b) = get_pending_fees(&position_data, &pool, &tick_lower, &tick_upper);
+        let rewards =
+            get_pending_rewards(client, &position_data, &pool, &tick_lower, &tick_upper).await?;
 
-    let parsed_position = ParsedPosition {
-        total_value_usd,
-        protocol: ProtocolInfo {
-            name: metadata_protocol.name,
-            symbol: metadata_protocol.symbol,
-            image: image_protocol,
-            pool_id: pool_id.to_string(),
-        },
-        token_a: TokenPosition {
-            mint: mint_a.to_string(),
-            name: metadata_token_a.name,
-            symbol: metadata_token_a.symbol,
-            image: image_a,
-            amount: FormattedAmountWithPrice {
-                amount: FormattedAmount {
-                    amount: amount_a.to_string(),
-                    formatted: formatted_amount_a,
-                },
-                price: price_a.unwrap(),
+        let fees = [
+            (pool.mint_a, pool.mint_decimals_a, fees_a),
+            (pool.mint_b, pool.mint_decimals_b, fees_b),
+        ]
+        .into_iter()
+        .filter(|(_, _, amount)| *amount > 0)
+        .map(|(mint, decimals, amount)| PositionToken {
+            mint,
+            decimals,
+            amount: amount as f64,
+        })
+        .collect();
+
+        Ok(PositionSnapshot {
+            mint: token.map(|token| token.mint.clone()),
+            pool_id,
+            token_a: PositionToken {
+                mint: pool.mint_a,
+                decimals: pool.mint_decimals_a,
+                amount: amount_a,
             },
-        },
-        token_b: TokenPosition {
-            mint: mint_b.to_string(),
-            name: metadata_token_b.name,
-            symbol: metadata_token_b.symbol,
-            image: image_b,
-            amount: FormattedAmountWithPrice {
-                amount: FormattedAmount {
-                    amount: amount_b.to_string(),
-                    formatted: formatted_amount_b,
-                },
-                price: price_b.unwrap(),
+            token_b: PositionToken {
+                mint: pool.mint_b,
+                decimals: pool.mint_decimals_b,
+                amount: amount_b,
             },
-        },
-        // TODO: not sure
-        fee_tier: String::new(),
-    };
-
-    Ok(parsed_position)
+            fee_tier: format_fee_rate(amm_config.trade_fee_rate),
+            price_range: Some(PriceRange {
+                lower: get_price_from_tick(position_data.tick_lower),
+                upper: get_price_from_tick(position_data.tick_upper),
+                current: get_sqrt_price_from_sqrt_price_x64(pool.sqrt_price_x64).powi(2),
+            }),
+            fees,
+            rewards,
+        })
+    }
 }
 
 pub fn get_position_address(nft_mint: &str) -> Result<Pubkey, Error> {
-    let nft_mint_pubkey = Pubkey::from_str(nft_mint).unwrap();
+    let nft_mint_pubkey = Pubkey::from_str(nft_mint).map_err(|_| Error::InvalidAddress)?;
     let program_id = Pubkey::from_str(RAYDIUM_CL_PROGRAM_ID).unwrap();
     let seeds = &[b"position", nft_mint_pubkey.as_ref()];
     let (position_address, _bump) = Pubkey::find_program_address(seeds, &program_id);
     Ok(position_address)
 }
 
-async fn get_position_data(
+/// Loads the lower and upper ticks of the position, which hold the
+/// fee and reward growth that happened outside of its range
+async fn get_tick_states(
     client: &SolanaMirrorClient,
-    position_address: &Pubkey,
-) -> Result<Position, Error> {
-    let encoded_position = match client
-        .get_account_info(
-            position_address,
-            Some(GetAccountDataConfig {
-                commitment: None,
-                encoding: Some("jsonParsed".to_string()),
-            }),
-        )
-        .await
+    pool_id: &Pubkey,
+    pool: &Pool,
+    position: &Position,
+) -> Result<(TickState, TickState), Error> {
+    let ticks = [position.tick_lower, position.tick_upper];
+    let start_indexes = ticks.map(|tick| get_tick_array_start_index(tick, pool.tick_spacing));
+    let addresses = start_indexes.map(|start_index| get_tick_array_address(pool_id, start_index));
+
+    let tick_arrays = client.get_multiple_accounts(&addresses).await?;
+
+    let mut tick_states = Vec::with_capacity(2);
+    for (((tick, start_index), address), tick_array) in ticks
+        .into_iter()
+        .zip(start_indexes)
+        .zip(addresses)
+        .zip(tick_arrays)
     {
-        Ok(encoded_position) => encoded_position,
-        Err(_) => return Ok(Position::default()),
+        let tick_array = tick_array.ok_or_else(|| {
+            Error::parse_error()
+                .with_source("tick array not found")
+                .with_address(address)
+        })?;
+
+        let offset = (tick - start_index) as usize / pool.tick_spacing as usize;
+        let start = TICK_ARRAY_TICKS_OFFSET + offset * TICK_STATE_LEN;
+        let tick_data = tick_array
+            .data
+            .get(start..start + TICK_STATE_LEN)
+            .ok_or_else(|| {
+                Error::parse_error()
+                    .with_source("tick array is too short")
+                    .with_address(address)
+            })?;
+
+        tick_states.push(decode_data::<TickState>(tick_data, &address)?);
+    }
+
+    let tick_upper = tick_states.pop().unwrap();
+    let tick_lower = tick_states.pop().unwrap();
+    Ok((tick_lower, tick_upper))
+}
+
+pub fn get_tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    let mut start = tick / ticks_in_array;
+    if tick < 0 && tick % ticks_in_array != 0 {
+        start -= 1;
+    }
+    start * ticks_in_array
+}
+
+pub fn get_tick_array_address(pool_id: &Pubkey, start_index: i32) -> Pubkey {
+    let program_id = Pubkey::from_str(RAYDIUM_CL_PROGRAM_ID).unwrap();
+    let seeds = &[
+        b"tick_array".as_ref(),
+        pool_id.as_ref(),
+        &start_index.to_be_bytes(),
+    ];
+    let (tick_array_address, _bump) = Pubkey::find_program_address(seeds, &program_id);
+    tick_array_address
+}
+
+/// Growth that happened inside the range: the global growth minus
+/// the growth below the lower tick and above the upper one
+fn get_growth_inside(
+    global: u128,
+    outside_lower: u128,
+    outside_upper: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+) -> u128 {
+    let below = if tick_current >= tick_lower {
+        outside_lower
+    } else {
+        global.wrapping_sub(outside_lower)
+    };
+    let above = if tick_current < tick_upper {
+        outside_upper
+    } else {
+        global.wrapping_sub(outside_upper)
     };
-    decode_data(&encoded_position)
+    global.wrapping_sub(below).wrapping_sub(above)
+}
+
+/// Fees owed at the last update plus the ones accrued since, in base units
+fn get_pending_fees(
+    position: &Position,
+    pool: &Pool,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+) -> (u64, u64) {
+    let pending_fee = |global, outside_lower, outside_upper, last, owed: u64| {
+        let inside = get_growth_inside(
+            global,
+            outside_lower,
+            outside_upper,
+            position.tick_lower,
+            position.tick_upper,
+            pool.tick_current,
+        );
+        owed.saturating_add(mul_shift_64(inside.wrapping_sub(last), position.liquidity))
+    };
+
+    let fees_a = pending_fee(
+        pool.fee_growth_global_x64_a,
+        tick_lower.fee_growth_outside_x64_a,
+        tick_upper.fee_growth_outside_x64_a,
+        position.fee_growth_inside_last_x64_a,
+        position.token_fees_owed_a,
+    );
+    let fees_b = pending_fee(
+        pool.fee_growth_global_x64_b,
+        tick_lower.fee_growth_outside_x64_b,
+        tick_upper.fee_growth_outside_x64_b,
+        position.fee_growth_inside_last_x64_b,
+        position.token_fees_owed_b,
+    );
+
+    (fees_a, fees_b)
+}
+
+/// Farm rewards owed to the position, per reward mint. The global growth stored in
+/// the pool is only updated on interactions, so the emissions since then are added
+async fn get_pending_rewards(
+    client: &SolanaMirrorClient,
+    position: &Position,
+    pool: &Pool,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+) -> Result<Vec<PositionToken>, Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut pending_rewards: Vec<(Pubkey, u64)> = Vec::new();
+    for (i, reward_info) in pool.reward_infos.iter().enumerate() {
+        if reward_info.token_mint == Pubkey::default() {
+            continue;
+        }
+
+        let mut growth_global = reward_info.reward_growth_global_x64;
+        let latest_update = now.min(reward_info.end_time);
+        if now > reward_info.open_time
+            && latest_update > reward_info.last_update_time
+            && pool.liquidity != 0
+        {
+            let elapsed = (latest_update - reward_info.last_update_time) as u128;
+            let growth_delta = match elapsed.checked_mul(reward_info.emissions_per_second_x64) {
+                Some(emitted) => emitted / pool.liquidity,
+                None => reward_info.emissions_per_second_x64 / pool.liquidity * elapsed,
+            };
+            growth_global = growth_global.wrapping_add(growth_delta);
+        }
+
+        let inside = get_growth_inside(
+            growth_global,
+            tick_lower.reward_growths_outside_x64[i],
+            tick_upper.reward_growths_outside_x64[i],
+            position.tick_lower,
+            position.tick_upper,
+            pool.tick_current,
+        );
+
+        let position_reward = &position.reward_infos[i];
+        let amount = position_reward
+            .reward_amount_owed
+            .saturating_add(mul_shift_64(
+                inside.wrapping_sub(position_reward.growth_inside_last_x64),
+                position.liquidity,
+            ));
+
+        if amount > 0 {
+            pending_rewards.push((reward_info.token_mint, amount));
+        }
+    }
+
+    let reward_futures = pending_rewards
+        .into_iter()
+        .map(|(mint, amount)| async move {
+            let decimals = client.get_decimals(&mint, None).await?;
+            Ok::<_, Error>(PositionToken {
+                mint,
+                decimals: decimals.result.value.decimals,
+                amount: amount as f64,
+            })
+        });
+
+    try_join_all(reward_futures).await
 }
 
 async fn get_pool_data(client: &SolanaMirrorClient, pool_id: &Pubkey) -> Result<Pool, Error> {
@@ -138,13 +323,31 @@ async fn get_pool_data(client: &SolanaMirrorClient, pool_id: &Pubkey) -> Result<
             pool_id,
             Some(GetAccountDataConfig {
                 commitment: None,
-                encoding: Some("jsonParsed".to_string()),
+                encoding: Some("base64".to_string()),
+            }),
+        )
+        .await?;
+    decode_data(&encoded_pool, pool_id)
+}
+
+/// The fee rate is set on the config shared by every pool of the same tier
+async fn get_amm_config_data(
+    client: &SolanaMirrorClient,
+    amm_config: &Pubkey,
+) -> Result<AmmConfig, Error> {
+    let encoded_config = client
+        .get_account_info(
+            amm_config,
+            Some(GetAccountDataConfig {
+                commitment: None,
+                encoding: Some("base64".to_string()),
             }),
         )
         .await?;
-    decode_data(&encoded_pool)
+    decode_data(&encoded_config, amm_config)
 }
 
-fn decode_data<T: DeserializeOwned>(data: &[u8]) -> Result<T, Error> {
-    bincode::deserialize(data).map_err(|_| Error::ParseError)
+fn decode_data<T: DeserializeOwned>(data: &[u8], address: &Pubkey) -> Result<T, Error> {
+    bincode::deserialize(data)
+        .map_err(|e| Error::parse_error().with_source(e).with_address(address))
 }
@@ -86,3 +86,30 @@ pub struct RewardInfo {
     pub creator: Pubkey,
     pub reward_growth_global_x64: u128,
 }
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct TickState {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_x64_a: u128,
+    pub fee_growth_outside_x64_b: u128,
+    pub reward_growths_outside_x64: [u128; 3],
+    pub padding: [u32; 13],
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct AmmConfig {
+    pub discriminator: [u8; 8],
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    pub protocol_fee_rate: u32,
+    /// Hundredths of a basis point, 2500 is 0.25%
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+    pub padding_u32: u32,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 3],
+}
@@ -0,0 +1,340 @@
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{
+    balances::accounts::types::ParsedAta,
+    client::{
+        types::KeyedAccount, GetAccountDataConfig, ProgramAccountsFilter, SolanaMirrorClient,
+    },
+    utils::{read_pubkey, read_u64},
+    Error,
+};
+
+use super::{format_fee_rate, DappAdapter, PositionSnapshot, PositionToken};
+use types::{AmmInfo, CpmmPool};
+
+pub mod types;
+
+const RAYDIUM_AMM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+const RAYDIUM_CPMM_PROGRAM_ID: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
+
+/// Mint authorities of the LP tokens, PDAs of each program
+const RAYDIUM_AMM_V4_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+const RAYDIUM_CPMM_AUTHORITY: &str = "GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL";
+
+const AMM_V4_POOL_LEN: u64 = 752;
+const AMM_V4_LP_MINT_OFFSET: usize = 464;
+const CPMM_LP_MINT_OFFSET: usize = 136;
+/// Discriminator, bump, disable_create_pool and index come before the fee rate
+const CPMM_CONFIG_TRADE_FEE_RATE_OFFSET: usize = 8 + 1 + 1 + 2;
+
+/// Offsets inside SPL mint and token accounts
+const MINT_AUTHORITY_OFFSET: usize = 4;
+const MINT_SUPPLY_OFFSET: usize = 36;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// Raydium AMM v4 (constant product, OpenBook based) LP tokens
+pub struct AmmV4Adapter;
+
+#[rocket::async_trait]
+impl DappAdapter for AmmV4Adapter {
+    fn id(&self) -> &'static str {
+        "raydium-amm-v4"
+    }
+
+    fn name(&self) -> &'static str {
+        "Raydium AMM v4"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).unwrap()
+    }
+
+    fn detect(&self, account: &ParsedAta) -> Option<Pubkey> {
+        detect_lp_mint(account)
+    }
+
+    fn matches(&self, account: &KeyedAccount) -> bool {
+        is_minted_by(account, RAYDIUM_AMM_V4_AUTHORITY)
+    }
+
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error> {
+        let token = get_lp_token(token, position)?;
+        let (pool_id, pool): (Pubkey, AmmInfo) = find_pool(
+            client,
+            &self.program_id(),
+            vec![
+                ProgramAccountsFilter::DataSize(AMM_V4_POOL_LEN),
+                ProgramAccountsFilter::memcmp(AMM_V4_LP_MINT_OFFSET, &position.pubkey),
+            ],
+            &position.pubkey,
+        )
+        .await?;
+
+        let (vault_a, vault_b) =
+            get_vault_amounts(client, &pool.coin_vault, &pool.pc_vault).await?;
+
+        // The protocol's cut of the fees is in the vaults but doesn't belong to LPs
+        let reserve_a = vault_a.saturating_sub(pool.state_data.need_take_pnl_coin);
+        let reserve_b = vault_b.saturating_sub(pool.state_data.need_take_pnl_pc);
+
+        let share = get_share(token, position)?;
+
+        let fee_rate = pool.fees.trade_fee_numerator as f64 * 1_000_000.0
+            / pool.fees.trade_fee_denominator.max(1) as f64;
+
+        Ok(PositionSnapshot {
+            mint: Some(token.mint.clone()),
+            pool_id,
+            token_a: PositionToken {
+                mint: pool.coin_vault_mint,
+                decimals: pool.coin_decimals as u8,
+                amount: (reserve_a as f64 * share).floor(),
+            },
+            token_b: PositionToken {
+                mint: pool.pc_vault_mint,
+                decimals: pool.pc_decimals as u8,
+                amount: (reserve_b as f64 * share).floor(),
+            },
+            fee_tier: format_fee_rate(fee_rate.round() as u32),
+            price_range: None,
+            fees: Vec::new(),
+            rewards: Vec::new(),
+        })
+    }
+}
+
+/// Raydium CPMM (constant product, no orderbook) LP tokens
+pub struct CpmmAdapter;
+
+#[rocket::async_trait]
+impl DappAdapter for CpmmAdapter {
+    fn id(&self) -> &'static str {
+        "raydium-cpmm"
+    }
+
+    fn name(&self) -> &'static str {
+        "Raydium CPMM"
+    }
+
+    fn program_id(&self) -> Pubkey {
+        Pubkey::from_str(RAYDIUM_CPMM_PROGRAM_ID).unwrap()
+    }
+
+    fn detect(&self, account: &ParsedAta) -> Option<Pubkey> {
+        detect_lp_mint(account)
+    }
+
+    fn matches(&self, account: &KeyedAccount) -> bool {
+        is_minted_by(account, RAYDIUM_CPMM_AUTHORITY)
+    }
+
+    async fn fetch(
+        &self,
+        client: &SolanaMirrorClient,
+        token: Option<&ParsedAta>,
+        position: &KeyedAccount,
+    ) -> Result<PositionSnapshot, Error> {
+        let token = get_lp_token(token, position)?;
+        let (pool_id, pool): (Pubkey, CpmmPool) = find_pool(
+            client,
+            &self.program_id(),
+            vec![ProgramAccountsFilter::memcmp(
+                CPMM_LP_MINT_OFFSET,
+                &position.pubkey,
+            )],
+            &position.pubkey,
+        )
+        .await?;
+
+        let (vault_a, vault_b) =
+            get_vault_amounts(client, &pool.token_0_vault, &pool.token_1_vault).await?;
+
+        // Protocol and fund fees are in the vaults but don't belong to LPs
+        let reserve_a = vault_a
+            .saturating_sub(pool.protocol_fees_token_0)
+            .saturating_sub(pool.fund_fees_token_0);
+        let reserve_b = vault_b
+            .saturating_sub(pool.protocol_fees_token_1)
+            .saturating_sub(pool.fund_fees_token_1);
+
+        let share = get_share(token, position)?;
+
+        let config = client
+            .get_account_info(
+                &pool.amm_config,
+                Some(GetAccountDataConfig {
+                    commitment: None,
+                    encoding: Some("base64".to_string()),
+                }),
+            )
+            .await?;
+        let trade_fee_rate = read_u64(&config, CPMM_CONFIG_TRADE_FEE_RATE_OFFSET)
+            .ok_or_else(|| too_short(&pool.amm_config))?;
+
+        Ok(PositionSnapshot {
+            mint: Some(token.mint.clone()),
+            pool_id,
+            token_a: PositionToken {
+                mint: pool.token_0_mint,
+                decimals: pool.mint_0_decimals,
+                amount: (reserve_a as f64 * share).floor(),
+            },
+            token_b: PositionToken {
+                mint: pool.token_1_mint,
+                decimals: pool.mint_1_decimals,
+                amount: (reserve_b as f64 * share).floor(),
+            },
+            fee_tier: format_fee_rate(trade_fee_rate as u32),
+            price_range: None,
+            fees: Vec::new(),
+            rewards: Vec::new(),
+        })
+    }
+}
+
+/// LP tokens are fungible, so any token with a balance could be one. The mint is
+/// probed and `matches` checks its authority
+fn detect_lp_mint(account: &ParsedAta) -> Option<Pubkey> {
+    if account.balance.amount == "0" {
+        return None;
+    }
+    Pubkey::from_str(&account.mint).ok()
+}
+
+fn is_minted_by(mint: &KeyedAccount, authority: &str) -> bool {
+    // The authority is a `COption<Pubkey>`, a u32 tag followed by the key
+    let has_authority = mint.data.get(..MINT_AUTHORITY_OFFSET) == Some(&[1, 0, 0, 0]);
+    has_authority
+        && read_pubkey(&mint.data, MINT_AUTHORITY_OFFSET)
+            .is_some_and(|key| key.to_string() == authority)
+}
+
+/// LP positions are always detected through the token the wallet holds
+fn get_lp_token<'a>(
+    token: Option<&'a ParsedAta>,
+    mint: &KeyedAccount,
+) -> Result<&'a ParsedAta, Error> {
+    token.ok_or_else(|| {
+        Error::parse_error()
+            .with_source("LP positions need the token account holding them")
+            .with_address(mint.pubkey)
+    })
+}
+
+/// Share of the pool owned by the wallet, from its balance and the LP supply
+fn get_share(token: &ParsedAta, mint: &KeyedAccount) -> Result<f64, Error> {
+    let supply = read_u64(&mint.data, MINT_SUPPLY_OFFSET).ok_or_else(|| too_short(&mint.pubkey))?;
+    if supply == 0 {
+        return Ok(0.0);
+    }
+
+    let balance = token.balance.amount.parse::<u64>().unwrap_or_default();
+    Ok(balance as f64 / supply as f64)
+}
+
+/// Pool lookups by LP mint, so each LP token only scans its program once
+enum CachedPool {
+    /// A pool never changes its LP mint, so it's kept for good
+    Found(Pubkey),
+    /// Scanned without finding a pool, retried after `MISSING_POOL_TTL`
+    Missing(Instant),
+}
+
+const MISSING_POOL_TTL: Duration = Duration::from_secs(10 * 60);
+
+static POOLS_BY_LP_MINT: Lazy<Mutex<HashMap<Pubkey, CachedPool>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Finds the pool that issues the given LP mint. The program is only scanned
+/// the first time, afterwards the pool is read directly
+async fn find_pool<T: DeserializeOwned>(
+    client: &SolanaMirrorClient,
+    program_id: &Pubkey,
+    filters: Vec<ProgramAccountsFilter>,
+    lp_mint: &Pubkey,
+) -> Result<(Pubkey, T), Error> {
+    let no_pool = || {
+        Error::parse_error()
+            .with_source("no pool found for the LP mint")
+            .with_address(lp_mint)
+    };
+
+    let cached = match POOLS_BY_LP_MINT.lock().unwrap().get(lp_mint) {
+        Some(CachedPool::Found(pool_id)) => Some(*pool_id),
+        Some(CachedPool::Missing(at)) if at.elapsed() < MISSING_POOL_TTL => return Err(no_pool()),
+        _ => None,
+    };
+
+    let (pool_id, data) = match cached {
+        Some(pool_id) => {
+            let data = client
+                .get_account_info(
+                    &pool_id,
+                    Some(GetAccountDataConfig {
+                        commitment: None,
+                        encoding: Some("base64".to_string()),
+                    }),
+                )
+                .await?;
+            (pool_id, data)
+        }
+        None => {
+            let pools = client
+                .get_program_accounts(program_id, filters, None)
+                .await?;
+
+            let mut cache = POOLS_BY_LP_MINT.lock().unwrap();
+            let Some(pool) = pools.into_iter().next() else {
+                cache.insert(*lp_mint, CachedPool::Missing(Instant::now()));
+                return Err(no_pool());
+            };
+            cache.insert(*lp_mint, CachedPool::Found(pool.pubkey));
+            (pool.pubkey, pool.data)
+        }
+    };
+
+    let decoded = bincode::deserialize(&data)
+        .map_err(|e| Error::parse_error().with_source(e).with_address(pool_id))?;
+
+    Ok((pool_id, decoded))
+}
+
+async fn get_vault_amounts(
+    client: &SolanaMirrorClient,
+    vault_a: &Pubkey,
+    vault_b: &Pubkey,
+) -> Result<(u64, u64), Error> {
+    let vaults = client.get_multiple_accounts(&[*vault_a, *vault_b]).await?;
+
+    let amount = |vault: Option<&KeyedAccount>, address: &Pubkey| {
+        vault
+            .and_then(|vault| read_u64(&vault.data, TOKEN_ACCOUNT_AMOUNT_OFFSET))
+            .ok_or_else(|| {
+                Error::parse_error()
+                    .with_source("vault not found")
+                    .with_address(address)
+            })
+    };
+
+    Ok((
+        amount(vaults[0].as_ref(), vault_a)?,
+        amount(vaults[1].as_ref(), vault_b)?,
+    ))
+}
+
+fn too_short(address: &Pubkey) -> Error {
+    Error::parse_error()
+        .with_source("account data is too short")
+        .with_address(address)
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use futures::future::{join, join_all};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::accounts::types::ParsedAta,
+    client::{types::KeyedAccount, SolanaMirrorClient},
+    Error,
+};
+
+use super::{
+    meteora::MeteoraDlmmAdapter,
+    orca::OrcaAdapter,
+    raydium::RaydiumAdapter,
+    raydium_amm::{AmmV4Adapter, CpmmAdapter},
+    types::ParsedPosition,
+    DappAdapter,
+};
+
+/// A token of the wallet attributed to a protocol
+pub struct DetectedPosition<'a> {
+    pub adapter: &'a dyn DappAdapter,
+    /// The token account that represents the position, `None` if it was discovered by owner
+    pub token: Option<&'a ParsedAta>,
+    /// The account returned by `detect`
+    pub account: KeyedAccount,
+}
+
+/// The protocols positions are looked up in
+pub struct DappRegistry {
+    adapters: Vec<Box<dyn DappAdapter>>,
+}
+
+impl DappRegistry {
+    /// A registry without any protocol
+    pub fn new() -> Self {
+        Self {
+            adapters: Vec::new(),
+        }
+    }
+
+    pub fn with_adapter<A: DappAdapter + 'static>(mut self, adapter: A) -> Self {
+        self.adapters.push(Box::new(adapter));
+        self
+    }
+
+    /// Finds the positions of the wallet: the token accounts that turn out to be positions,
+    /// and if `discover` is set, the ones its protocols discover by owner.
+    /// Failures are logged and skipped, a protocol that can't be reached doesn't fail the balances
+    pub async fn detect<'a>(
+        &'a self,
+        client: &SolanaMirrorClient,
+        owner: &Pubkey,
+        accounts: &'a [ParsedAta],
+        discover: bool,
+    ) -> Vec<DetectedPosition<'a>> {
+        let discover_future = async {
+            if discover {
+                self.discover(client, owner).await
+            } else {
+                Vec::new()
+            }
+        };
+        let (detected, discovered) =
+            join(self.detect_tokens(client, accounts), discover_future).await;
+
+        let mut detected = detected.unwrap_or_else(|e| {
+            eprintln!("Failed to detect the positions of {}: {}", owner, e);
+            Vec::new()
+        });
+        detected.extend(discovered);
+        detected
+    }
+
+    async fn discover(
+        &self,
+        client: &SolanaMirrorClient,
+        owner: &Pubkey,
+    ) -> Vec<DetectedPosition<'_>> {
+        let discover_futures = self.adapters.iter().map(|adapter| async move {
+            let accounts = adapter.discover(client, owner).await.unwrap_or_else(|e| {
+                eprintln!(
+                    "Failed to discover the {} positions of {}: {}",
+                    adapter.id(),
+                    owner,
+                    e
+                );
+                Vec::new()
+            });
+
+            accounts.into_iter().map(|account| DetectedPosition {
+                adapter: adapter.as_ref(),
+                token: None,
+                account,
+            })
+        });
+
+        join_all(discover_futures)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Derives the position account every protocol would use for each token,
+    /// and checks which program owns the ones that exist
+    async fn detect_tokens<'a>(
+        &'a self,
+        client: &SolanaMirrorClient,
+        accounts: &'a [ParsedAta],
+    ) -> Result<Vec<DetectedPosition<'a>>, Error> {
+        let candidates: Vec<_> = accounts
+            .iter()
+            .flat_map(|account| {
+                self.adapters.iter().filter_map(move |adapter| {
+                    adapter
+                        .detect(account)
+                        .map(|address| (adapter.as_ref(), account, address))
+                })
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Several protocols can ask for the same account (eg. the mint itself)
+        let mut addresses: Vec<Pubkey> = Vec::new();
+        for (_, _, address) in &candidates {
+            if !addresses.contains(address) {
+                addresses.push(*address);
+            }
+        }
+
+        let probed: HashMap<Pubkey, KeyedAccount> = client
+            .get_multiple_accounts(&addresses)
+            .await?
+            .into_iter()
+            .flatten()
+            .map(|account| (account.pubkey, account))
+            .collect();
+
+        let mut detected: Vec<DetectedPosition> = Vec::new();
+        for (adapter, token, address) in candidates {
+            let Some(probed) = probed.get(&address) else {
+                continue;
+            };
+
+            // A token is attributed to the first protocol that claims it
+            let is_claimed = detected
+                .iter()
+                .any(|d| d.token.is_some_and(|t| t.ata == token.ata));
+            if !adapter.matches(probed) || is_claimed {
+                continue;
+            }
+
+            detected.push(DetectedPosition {
+                adapter,
+                token: Some(token),
+                account: probed.clone(),
+            });
+        }
+
+        Ok(detected)
+    }
+
+    /// Decodes and prices the detected positions, each result matches the position at its index
+    pub async fn get_positions(
+        &self,
+        client: &SolanaMirrorClient,
+        detected: &[DetectedPosition<'_>],
+    ) -> Vec<Result<ParsedPosition, Error>> {
+        let position_futures = detected.iter().map(|position| async move {
+            let snapshot = position
+                .adapter
+                .fetch(client, position.token, &position.account)
+                .await?;
+            Ok(position.adapter.value(client, snapshot).await)
+        });
+
+        join_all(position_futures).await
+    }
+}
+
+impl Default for DappRegistry {
+    /// Every supported protocol
+    fn default() -> Self {
+        Self::new()
+            .with_adapter(RaydiumAdapter)
+            .with_adapter(OrcaAdapter)
+            .with_adapter(AmmV4Adapter)
+            .with_adapter(CpmmAdapter)
+            .with_adapter(MeteoraDlmmAdapter)
+    }
+}
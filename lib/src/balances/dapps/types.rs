@@ -1,10 +1,13 @@
-use crate::types::FormattedAmountWithPrice;
+use crate::{price::PriceQuote, types::FormattedAmountWithPrice};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct ParsedPosition {
     #[serde(rename = "totalValueUsd")]
     pub total_value_usd: Option<f64>,
+    /// Share of the wallet gross value (`totals.gross`), between 0 and 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
     pub protocol: ProtocolInfo,
     #[serde(rename = "tokenA")]
     pub token_a: TokenPosition,
@@ -12,6 +15,28 @@ pub struct ParsedPosition {
     pub token_b: TokenPosition,
     #[serde(rename = "feeTier")]
     pub fee_tier: String,
+    /// Prices of token A in token B where the liquidity is active
+    #[serde(rename = "priceLower", skip_serializing_if = "Option::is_none")]
+    pub price_lower: Option<f64>,
+    #[serde(rename = "priceUpper", skip_serializing_if = "Option::is_none")]
+    pub price_upper: Option<f64>,
+    #[serde(rename = "currentPrice", skip_serializing_if = "Option::is_none")]
+    pub current_price: Option<f64>,
+    /// Whether the pool price is inside the range, earning fees
+    #[serde(rename = "inRange", skip_serializing_if = "Option::is_none")]
+    pub in_range: Option<bool>,
+    #[serde(
+        rename = "unclaimedFees",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub unclaimed_fees: Vec<TokenPosition>,
+    #[serde(
+        rename = "unclaimedRewards",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub unclaimed_rewards: Vec<TokenPosition>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default)]
@@ -21,10 +46,14 @@ pub struct TokenPosition {
     pub symbol: String,
     pub image: String,
     pub amount: FormattedAmountWithPrice,
+    #[serde(rename = "priceQuote", skip_serializing_if = "Option::is_none")]
+    pub price_quote: Option<PriceQuote>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct ProtocolInfo {
+    /// Key of the protocol in the registry, eg. `raydium` or `orca`
+    pub id: String,
     pub name: String,
     pub symbol: String,
     pub image: String,
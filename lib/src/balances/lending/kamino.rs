@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::dapps::PositionToken,
+    client::{types::KeyedAccount, ProgramAccountsFilter, SolanaMirrorClient},
+    utils::{read_pubkey, read_u128, read_u64},
+    Error,
+};
+
+use super::{invalid_account, RawObligation};
+
+const KAMINO_LEND_PROGRAM_ID: &str = "KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD";
+
+const OBLIGATION_LEN: u64 = 3344;
+const OBLIGATION_MARKET_OFFSET: usize = 32;
+const OBLIGATION_OWNER_OFFSET: usize = 64;
+
+const DEPOSITS_OFFSET: usize = 96;
+const DEPOSIT_LEN: usize = 136;
+const MAX_DEPOSITS: usize = 8;
+
+const BORROWS_OFFSET: usize = 1208;
+const BORROW_LEN: usize = 200;
+const MAX_BORROWS: usize = 5;
+
+const BORROW_FACTOR_ADJUSTED_DEBT_OFFSET: usize = 2208;
+const UNHEALTHY_BORROW_VALUE_OFFSET: usize = 2256;
+
+const RESERVE_MINT_OFFSET: usize = 128;
+const RESERVE_PRICE_OFFSET: usize = 248;
+const RESERVE_DECIMALS_OFFSET: usize = 272;
+
+/// Kamino stores decimals as fixed point numbers with 60 fractional bits
+const SF_SCALE: f64 = (1_u128 << 60) as f64;
+
+struct Deposit {
+    reserve: Pubkey,
+    market_value_sf: u128,
+}
+
+struct Borrow {
+    reserve: Pubkey,
+    borrowed_amount_sf: u128,
+}
+
+struct Reserve {
+    mint: Pubkey,
+    decimals: u8,
+    /// USD price of a whole token
+    market_price_sf: u128,
+}
+
+pub async fn get_obligations(
+    client: &SolanaMirrorClient,
+    owner: &Pubkey,
+) -> Result<Vec<RawObligation>, Error> {
+    let obligations = client
+        .get_program_accounts(
+            &Pubkey::from_str(KAMINO_LEND_PROGRAM_ID).unwrap(),
+            vec![
+                ProgramAccountsFilter::DataSize(OBLIGATION_LEN),
+                ProgramAccountsFilter::memcmp(OBLIGATION_OWNER_OFFSET, owner),
+            ],
+            None,
+        )
+        .await?;
+
+    if obligations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let decoded = obligations
+        .iter()
+        .map(|obligation| {
+            decode_obligation(obligation).ok_or_else(|| invalid_account(&obligation.pubkey))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut reserve_addresses: Vec<Pubkey> = Vec::new();
+    for (deposits, borrows, _) in &decoded {
+        let reserves = deposits
+            .iter()
+            .map(|d| d.reserve)
+            .chain(borrows.iter().map(|b| b.reserve));
+        for reserve in reserves {
+            if !reserve_addresses.contains(&reserve) {
+                reserve_addresses.push(reserve);
+            }
+        }
+    }
+
+    let reserves: HashMap<Pubkey, Reserve> = client
+        .get_multiple_accounts(&reserve_addresses)
+        .await?
+        .into_iter()
+        .flatten()
+        .filter_map(|account| decode_reserve(&account).map(|reserve| (account.pubkey, reserve)))
+        .collect();
+
+    let mut raw_obligations = Vec::new();
+    for (obligation, (deposits, borrows, health_factor)) in obligations.iter().zip(decoded) {
+        let reserve = |address: &Pubkey| {
+            reserves
+                .get(address)
+                .ok_or_else(|| invalid_account(address))
+        };
+
+        // Deposits are stored as collateral tokens, their value gives the underlying amount
+        let mut supplied = Vec::new();
+        for deposit in deposits {
+            let reserve = reserve(&deposit.reserve)?;
+            let amount = match reserve.market_price_sf {
+                0 => 0.0,
+                price => {
+                    deposit.market_value_sf as f64 / price as f64
+                        * 10_f64.powi(reserve.decimals as i32)
+                }
+            };
+            supplied.push(PositionToken {
+                mint: reserve.mint,
+                decimals: reserve.decimals,
+                amount: amount.floor(),
+            });
+        }
+
+        let mut borrowed = Vec::new();
+        for borrow in borrows {
+            let reserve = reserve(&borrow.reserve)?;
+            borrowed.push(PositionToken {
+                mint: reserve.mint,
+                decimals: reserve.decimals,
+                amount: (borrow.borrowed_amount_sf as f64 / SF_SCALE).ceil(),
+            });
+        }
+
+        raw_obligations.push(RawObligation {
+            protocol: "kamino",
+            name: "Kamino Lend",
+            address: obligation.pubkey,
+            market: read_pubkey(&obligation.data, OBLIGATION_MARKET_OFFSET)
+                .ok_or_else(|| invalid_account(&obligation.pubkey))?,
+            supplied_weights: vec![1.0; supplied.len()],
+            borrowed_weights: vec![1.0; borrowed.len()],
+            supplied,
+            borrowed,
+            health_factor,
+        });
+    }
+
+    Ok(raw_obligations)
+}
+
+/// Reads the used deposit and borrow slots, and the health factor from the values
+/// computed by the program on the last refresh
+fn decode_obligation(
+    obligation: &KeyedAccount,
+) -> Option<(Vec<Deposit>, Vec<Borrow>, Option<f64>)> {
+    let data = &obligation.data;
+
+    let mut deposits = Vec::new();
+    for i in 0..MAX_DEPOSITS {
+        let offset = DEPOSITS_OFFSET + i * DEPOSIT_LEN;
+        let reserve = read_pubkey(data, offset)?;
+        if reserve == Pubkey::default() {
+            continue;
+        }
+        deposits.push(Deposit {
+            reserve,
+            market_value_sf: read_u128(data, offset + 40)?,
+        });
+    }
+
+    let mut borrows = Vec::new();
+    for i in 0..MAX_BORROWS {
+        let offset = BORROWS_OFFSET + i * BORROW_LEN;
+        let reserve = read_pubkey(data, offset)?;
+        if reserve == Pubkey::default() {
+            continue;
+        }
+        borrows.push(Borrow {
+            reserve,
+            borrowed_amount_sf: read_u128(data, offset + 88)?,
+        });
+    }
+
+    let debt = read_u128(data, BORROW_FACTOR_ADJUSTED_DEBT_OFFSET)?;
+    let unhealthy_borrow_value = read_u128(data, UNHEALTHY_BORROW_VALUE_OFFSET)?;
+    let health_factor = (debt > 0).then(|| unhealthy_borrow_value as f64 / debt as f64);
+
+    Some((deposits, borrows, health_factor))
+}
+
+fn decode_reserve(reserve: &KeyedAccount) -> Option<Reserve> {
+    Some(Reserve {
+        mint: read_pubkey(&reserve.data, RESERVE_MINT_OFFSET)?,
+        decimals: read_u64(&reserve.data, RESERVE_DECIMALS_OFFSET)? as u8,
+        market_price_sf: read_u128(&reserve.data, RESERVE_PRICE_OFFSET)?,
+    })
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::dapps::PositionToken,
+    client::{types::KeyedAccount, ProgramAccountsFilter, SolanaMirrorClient},
+    utils::{read_pubkey, read_u128},
+    Error,
+};
+
+use super::{invalid_account, RawObligation};
+
+const MARGINFI_PROGRAM_ID: &str = "MFv2hWf31Z9kbCa1snEPYctwafyhdvnV7FZnsebVacA";
+
+const ACCOUNT_LEN: u64 = 2312;
+const ACCOUNT_GROUP_OFFSET: usize = 8;
+const ACCOUNT_AUTHORITY_OFFSET: usize = 40;
+
+const BALANCES_OFFSET: usize = 72;
+const BALANCE_LEN: usize = 104;
+const MAX_BALANCES: usize = 16;
+
+const BANK_MINT_OFFSET: usize = 8;
+const BANK_DECIMALS_OFFSET: usize = 40;
+const BANK_ASSET_SHARE_VALUE_OFFSET: usize = 80;
+const BANK_LIABILITY_SHARE_VALUE_OFFSET: usize = 96;
+const BANK_ASSET_WEIGHT_MAINT_OFFSET: usize = 312;
+const BANK_LIABILITY_WEIGHT_MAINT_OFFSET: usize = 344;
+
+struct Balance {
+    bank: Pubkey,
+    asset_shares: f64,
+    liability_shares: f64,
+}
+
+struct Bank {
+    mint: Pubkey,
+    decimals: u8,
+    asset_share_value: f64,
+    liability_share_value: f64,
+    asset_weight_maint: f64,
+    liability_weight_maint: f64,
+}
+
+pub async fn get_obligations(
+    client: &SolanaMirrorClient,
+    owner: &Pubkey,
+) -> Result<Vec<RawObligation>, Error> {
+    let accounts = client
+        .get_program_accounts(
+            &Pubkey::from_str(MARGINFI_PROGRAM_ID).unwrap(),
+            vec![
+                ProgramAccountsFilter::DataSize(ACCOUNT_LEN),
+                ProgramAccountsFilter::memcmp(ACCOUNT_AUTHORITY_OFFSET, owner),
+            ],
+            None,
+        )
+        .await?;
+
+    if accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let decoded = accounts
+        .iter()
+        .map(|account| decode_balances(account).ok_or_else(|| invalid_account(&account.pubkey)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut bank_addresses: Vec<Pubkey> = Vec::new();
+    for balance in decoded.iter().flatten() {
+        if !bank_addresses.contains(&balance.bank) {
+            bank_addresses.push(balance.bank);
+        }
+    }
+
+    let banks: HashMap<Pubkey, Bank> = client
+        .get_multiple_accounts(&bank_addresses)
+        .await?
+        .into_iter()
+        .flatten()
+        .filter_map(|account| decode_bank(&account).map(|bank| (account.pubkey, bank)))
+        .collect();
+
+    let mut raw_obligations = Vec::new();
+    for (account, balances) in accounts.iter().zip(decoded) {
+        let mut obligation = RawObligation {
+            protocol: "marginfi",
+            name: "marginfi",
+            address: account.pubkey,
+            market: read_pubkey(&account.data, ACCOUNT_GROUP_OFFSET)
+                .ok_or_else(|| invalid_account(&account.pubkey))?,
+            supplied: Vec::new(),
+            borrowed: Vec::new(),
+            supplied_weights: Vec::new(),
+            borrowed_weights: Vec::new(),
+            health_factor: None,
+        };
+
+        for balance in balances {
+            let bank = banks
+                .get(&balance.bank)
+                .ok_or_else(|| invalid_account(&balance.bank))?;
+
+            // Shares grow into more underlying tokens as interest accrues
+            let supplied = balance.asset_shares * bank.asset_share_value;
+            if supplied >= 1.0 {
+                obligation.supplied.push(PositionToken {
+                    mint: bank.mint,
+                    decimals: bank.decimals,
+                    amount: supplied.floor(),
+                });
+                obligation.supplied_weights.push(bank.asset_weight_maint);
+            }
+
+            let borrowed = balance.liability_shares * bank.liability_share_value;
+            if borrowed >= 1.0 {
+                obligation.borrowed.push(PositionToken {
+                    mint: bank.mint,
+                    decimals: bank.decimals,
+                    amount: borrowed.ceil(),
+                });
+                obligation
+                    .borrowed_weights
+                    .push(bank.liability_weight_maint);
+            }
+        }
+
+        raw_obligations.push(obligation);
+    }
+
+    Ok(raw_obligations)
+}
+
+/// Reads an `I80F48` fixed point number, a signed integer with 48 fractional bits
+fn read_i80f48(data: &[u8], offset: usize) -> Option<f64> {
+    let value = read_u128(data, offset)? as i128;
+    Some(value as f64 / (1_u64 << 48) as f64)
+}
+
+/// Reads the active balance slots of a marginfi account
+fn decode_balances(account: &KeyedAccount) -> Option<Vec<Balance>> {
+    let data = &account.data;
+
+    let mut balances = Vec::new();
+    for i in 0..MAX_BALANCES {
+        let offset = BALANCES_OFFSET + i * BALANCE_LEN;
+        if *data.get(offset)? == 0 {
+            continue;
+        }
+        balances.push(Balance {
+            bank: read_pubkey(data, offset + 1)?,
+            asset_shares: read_i80f48(data, offset + 40)?,
+            liability_shares: read_i80f48(data, offset + 56)?,
+        });
+    }
+
+    Some(balances)
+}
+
+fn decode_bank(bank: &KeyedAccount) -> Option<Bank> {
+    let data = &bank.data;
+
+    Some(Bank {
+        mint: read_pubkey(data, BANK_MINT_OFFSET)?,
+        decimals: *data.get(BANK_DECIMALS_OFFSET)?,
+        asset_share_value: read_i80f48(data, BANK_ASSET_SHARE_VALUE_OFFSET)?,
+        liability_share_value: read_i80f48(data, BANK_LIABILITY_SHARE_VALUE_OFFSET)?,
+        asset_weight_maint: read_i80f48(data, BANK_ASSET_WEIGHT_MAINT_OFFSET)?,
+        liability_weight_maint: read_i80f48(data, BANK_LIABILITY_WEIGHT_MAINT_OFFSET)?,
+    })
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use futures::future::{join3, join_all};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::dapps::PositionToken,
+    client::SolanaMirrorClient,
+    price::{get_prices_with_decimals, PriceQuote},
+    types::FormattedAmount,
+    utils::{fetch_image, fetch_metadata},
+    Error,
+};
+
+use types::{LendingAsset, LendingObligation};
+
+pub mod kamino;
+pub mod marginfi;
+pub mod solend;
+pub mod types;
+
+/// An obligation as decoded from chain, before pricing
+pub struct RawObligation {
+    pub protocol: &'static str,
+    pub name: &'static str,
+    pub address: Pubkey,
+    pub market: Pubkey,
+    pub supplied: Vec<PositionToken>,
+    pub borrowed: Vec<PositionToken>,
+    /// How much of each supplied asset counts as collateral before liquidation, 1 if unknown
+    pub supplied_weights: Vec<f64>,
+    /// How much each borrowed asset counts as debt, 1 if unknown
+    pub borrowed_weights: Vec<f64>,
+    /// Health factor as stored by the protocol, if it keeps one
+    pub health_factor: Option<f64>,
+}
+
+/// Fetches the deposits and borrows of the given address in every supported lending protocol.
+/// A protocol that fails is logged and left out, so it doesn't hide the other ones
+pub async fn get_lending_obligations(
+    client: &SolanaMirrorClient,
+    owner: &Pubkey,
+) -> Vec<LendingObligation> {
+    let (kamino, marginfi, solend) = join3(
+        kamino::get_obligations(client, owner),
+        marginfi::get_obligations(client, owner),
+        solend::get_obligations(client, owner),
+    )
+    .await;
+
+    let raw_obligations = [
+        ("kamino", kamino),
+        ("marginfi", marginfi),
+        ("solend", solend),
+    ]
+    .into_iter()
+    .flat_map(|(protocol, result)| {
+        result.unwrap_or_else(|e| {
+            eprintln!(
+                "Failed to fetch the {} obligations of {}: {}",
+                protocol, owner, e
+            );
+            Vec::new()
+        })
+    });
+
+    join_all(raw_obligations.map(|raw| parse_obligation(client, raw))).await
+}
+
+async fn parse_obligation(client: &SolanaMirrorClient, raw: RawObligation) -> LendingObligation {
+    let mints: Vec<(Pubkey, u8)> = raw
+        .supplied
+        .iter()
+        .chain(&raw.borrowed)
+        .map(|token| (token.mint, token.decimals))
+        .collect();
+    let prices = get_prices_with_decimals(client, &mints).await;
+
+    let supplied = join_all(
+        raw.supplied
+            .into_iter()
+            .map(|t| parse_asset(client, t, &prices)),
+    )
+    .await;
+    let borrowed = join_all(
+        raw.borrowed
+            .into_iter()
+            .map(|t| parse_asset(client, t, &prices)),
+    )
+    .await;
+
+    let value = |asset: &LendingAsset| asset.value_usd.unwrap_or_default();
+    let supplied_usd: f64 = supplied.iter().map(value).sum();
+    let borrowed_usd: f64 = borrowed.iter().map(value).sum();
+
+    // Protocols that don't store a health factor get it from the priced assets
+    let health_factor = raw.health_factor.or_else(|| {
+        let weighted_debt: f64 = borrowed
+            .iter()
+            .zip(&raw.borrowed_weights)
+            .map(|(asset, weight)| value(asset) * weight)
+            .sum();
+        let weighted_collateral: f64 = supplied
+            .iter()
+            .zip(&raw.supplied_weights)
+            .map(|(asset, weight)| value(asset) * weight)
+            .sum();
+
+        (weighted_debt > 0.0).then(|| weighted_collateral / weighted_debt)
+    });
+
+    LendingObligation {
+        protocol: raw.protocol.to_string(),
+        name: raw.name.to_string(),
+        address: raw.address.to_string(),
+        market: raw.market.to_string(),
+        supplied,
+        borrowed,
+        supplied_usd,
+        borrowed_usd,
+        net_value_usd: supplied_usd - borrowed_usd,
+        health_factor,
+    }
+}
+
+async fn parse_asset(
+    client: &SolanaMirrorClient,
+    token: PositionToken,
+    prices: &HashMap<Pubkey, PriceQuote>,
+) -> LendingAsset {
+    let metadata = fetch_metadata(client, &token.mint.to_string(), None).await;
+    let image = fetch_image(&metadata).await;
+    let quote = prices.get(&token.mint).cloned();
+    let price = quote.as_ref().map(|quote| quote.price);
+
+    let formatted = token.amount / 10_f64.powi(token.decimals as i32);
+
+    LendingAsset {
+        mint: token.mint.to_string(),
+        name: metadata.name,
+        symbol: metadata.symbol,
+        image,
+        balance: FormattedAmount {
+            amount: token.amount.to_string(),
+            formatted,
+        },
+        price,
+        value_usd: price.map(|price| formatted * price),
+        price_quote: quote,
+    }
+}
+
+/// Accounts that are missing or shorter than their layout
+fn invalid_account(address: &Pubkey) -> Error {
+    Error::parse_error()
+        .with_source("account not found or too short")
+        .with_address(address)
+}
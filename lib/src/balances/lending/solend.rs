@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::dapps::PositionToken,
+    client::{types::KeyedAccount, ProgramAccountsFilter, SolanaMirrorClient},
+    utils::{read_pubkey, read_u128, read_u64},
+    Error,
+};
+
+use super::{invalid_account, RawObligation};
+
+const SOLEND_PROGRAM_ID: &str = "So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo";
+
+const OBLIGATION_LEN: u64 = 1300;
+const OBLIGATION_MARKET_OFFSET: usize = 10;
+const OBLIGATION_OWNER_OFFSET: usize = 42;
+const OBLIGATION_BORROWED_VALUE_OFFSET: usize = 90;
+const OBLIGATION_UNHEALTHY_BORROW_VALUE_OFFSET: usize = 122;
+const OBLIGATION_DEPOSITS_LEN_OFFSET: usize = 202;
+const OBLIGATION_BORROWS_LEN_OFFSET: usize = 203;
+const OBLIGATION_DATA_OFFSET: usize = 204;
+
+const DEPOSIT_LEN: usize = 88;
+const BORROW_LEN: usize = 112;
+
+const RESERVE_MINT_OFFSET: usize = 42;
+const RESERVE_DECIMALS_OFFSET: usize = 74;
+const RESERVE_AVAILABLE_AMOUNT_OFFSET: usize = 171;
+const RESERVE_BORROWED_AMOUNT_OFFSET: usize = 179;
+const RESERVE_COLLATERAL_SUPPLY_OFFSET: usize = 259;
+
+/// Solend decimals carry 18 fractional digits
+const WAD: f64 = 1e18;
+
+struct Deposit {
+    reserve: Pubkey,
+    /// Amount of collateral tokens, redeemable for a growing amount of liquidity
+    deposited_amount: u64,
+}
+
+struct Borrow {
+    reserve: Pubkey,
+    borrowed_amount_wads: u128,
+}
+
+struct Reserve {
+    mint: Pubkey,
+    decimals: u8,
+    /// Liquidity tokens, available plus borrowed, backing every collateral token
+    total_liquidity: f64,
+    collateral_supply: u64,
+}
+
+pub async fn get_obligations(
+    client: &SolanaMirrorClient,
+    owner: &Pubkey,
+) -> Result<Vec<RawObligation>, Error> {
+    let obligations = client
+        .get_program_accounts(
+            &Pubkey::from_str(SOLEND_PROGRAM_ID).unwrap(),
+            vec![
+                ProgramAccountsFilter::DataSize(OBLIGATION_LEN),
+                ProgramAccountsFilter::memcmp(OBLIGATION_OWNER_OFFSET, owner),
+            ],
+            None,
+        )
+        .await?;
+
+    if obligations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let decoded = obligations
+        .iter()
+        .map(|obligation| {
+            decode_obligation(obligation).ok_or_else(|| invalid_account(&obligation.pubkey))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut reserve_addresses: Vec<Pubkey> = Vec::new();
+    for (deposits, borrows, _) in &decoded {
+        let reserves = deposits
+            .iter()
+            .map(|d| d.reserve)
+            .chain(borrows.iter().map(|b| b.reserve));
+        for reserve in reserves {
+            if !reserve_addresses.contains(&reserve) {
+                reserve_addresses.push(reserve);
+            }
+        }
+    }
+
+    let reserves: HashMap<Pubkey, Reserve> = client
+        .get_multiple_accounts(&reserve_addresses)
+        .await?
+        .into_iter()
+        .flatten()
+        .filter_map(|account| decode_reserve(&account).map(|reserve| (account.pubkey, reserve)))
+        .collect();
+
+    let mut raw_obligations = Vec::new();
+    for (obligation, (deposits, borrows, health_factor)) in obligations.iter().zip(decoded) {
+        let reserve = |address: &Pubkey| {
+            reserves
+                .get(address)
+                .ok_or_else(|| invalid_account(address))
+        };
+
+        let mut supplied = Vec::new();
+        for deposit in deposits {
+            let reserve = reserve(&deposit.reserve)?;
+            let amount = match reserve.collateral_supply {
+                0 => 0.0,
+                supply => deposit.deposited_amount as f64 * reserve.total_liquidity / supply as f64,
+            };
+            supplied.push(PositionToken {
+                mint: reserve.mint,
+                decimals: reserve.decimals,
+                amount: amount.floor(),
+            });
+        }
+
+        let mut borrowed = Vec::new();
+        for borrow in borrows {
+            let reserve = reserve(&borrow.reserve)?;
+            borrowed.push(PositionToken {
+                mint: reserve.mint,
+                decimals: reserve.decimals,
+                amount: (borrow.borrowed_amount_wads as f64 / WAD).ceil(),
+            });
+        }
+
+        raw_obligations.push(RawObligation {
+            protocol: "solend",
+            name: "Save",
+            address: obligation.pubkey,
+            market: read_pubkey(&obligation.data, OBLIGATION_MARKET_OFFSET)
+                .ok_or_else(|| invalid_account(&obligation.pubkey))?,
+            supplied_weights: vec![1.0; supplied.len()],
+            borrowed_weights: vec![1.0; borrowed.len()],
+            supplied,
+            borrowed,
+            health_factor,
+        });
+    }
+
+    Ok(raw_obligations)
+}
+
+/// Deposits and borrows are packed one after the other, their counts come right before them
+fn decode_obligation(
+    obligation: &KeyedAccount,
+) -> Option<(Vec<Deposit>, Vec<Borrow>, Option<f64>)> {
+    let data = &obligation.data;
+
+    let deposits_len = *data.get(OBLIGATION_DEPOSITS_LEN_OFFSET)? as usize;
+    let borrows_len = *data.get(OBLIGATION_BORROWS_LEN_OFFSET)? as usize;
+
+    let mut deposits = Vec::with_capacity(deposits_len);
+    for i in 0..deposits_len {
+        let offset = OBLIGATION_DATA_OFFSET + i * DEPOSIT_LEN;
+        deposits.push(Deposit {
+            reserve: read_pubkey(data, offset)?,
+            deposited_amount: read_u64(data, offset + 32)?,
+        });
+    }
+
+    let borrows_offset = OBLIGATION_DATA_OFFSET + deposits_len * DEPOSIT_LEN;
+    let mut borrows = Vec::with_capacity(borrows_len);
+    for i in 0..borrows_len {
+        let offset = borrows_offset + i * BORROW_LEN;
+        borrows.push(Borrow {
+            reserve: read_pubkey(data, offset)?,
+            borrowed_amount_wads: read_u128(data, offset + 48)?,
+        });
+    }
+
+    let borrowed_value = read_u128(data, OBLIGATION_BORROWED_VALUE_OFFSET)?;
+    let unhealthy_borrow_value = read_u128(data, OBLIGATION_UNHEALTHY_BORROW_VALUE_OFFSET)?;
+    let health_factor =
+        (borrowed_value > 0).then(|| unhealthy_borrow_value as f64 / borrowed_value as f64);
+
+    Some((deposits, borrows, health_factor))
+}
+
+fn decode_reserve(reserve: &KeyedAccount) -> Option<Reserve> {
+    let data = &reserve.data;
+
+    let available_amount = read_u64(data, RESERVE_AVAILABLE_AMOUNT_OFFSET)?;
+    let borrowed_amount_wads = read_u128(data, RESERVE_BORROWED_AMOUNT_OFFSET)?;
+
+    Some(Reserve {
+        mint: read_pubkey(data, RESERVE_MINT_OFFSET)?,
+        decimals: *data.get(RESERVE_DECIMALS_OFFSET)?,
+        total_liquidity: available_amount as f64 + borrowed_amount_wads as f64 / WAD,
+        collateral_supply: read_u64(data, RESERVE_COLLATERAL_SUPPLY_OFFSET)?,
+    })
+}
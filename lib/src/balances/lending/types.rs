@@ -0,0 +1,40 @@
+use serde::Serialize;
+
+use crate::{price::PriceQuote, types::FormattedAmount};
+
+/// Deposits and borrows of a wallet in one lending market
+#[derive(Debug, Serialize)]
+pub struct LendingObligation {
+    /// Key of the protocol, eg. `kamino`
+    pub protocol: String,
+    pub name: String,
+    /// The obligation (or account) that holds the positions
+    pub address: String,
+    pub market: String,
+    pub supplied: Vec<LendingAsset>,
+    pub borrowed: Vec<LendingAsset>,
+    #[serde(rename = "suppliedUsd")]
+    pub supplied_usd: f64,
+    #[serde(rename = "borrowedUsd")]
+    pub borrowed_usd: f64,
+    #[serde(rename = "netValueUsd")]
+    pub net_value_usd: f64,
+    /// Liquidation threshold weighted collateral over debt, liquidatable below 1.
+    /// `None` when nothing is borrowed
+    #[serde(rename = "healthFactor")]
+    pub health_factor: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LendingAsset {
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub image: String,
+    pub balance: FormattedAmount,
+    pub price: Option<f64>,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+    #[serde(rename = "priceQuote", skip_serializing_if = "Option::is_none")]
+    pub price_quote: Option<PriceQuote>,
+}
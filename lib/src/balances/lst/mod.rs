@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{join, join_all};
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    client::{DataSlice, GetAccountDataConfig, Memcmp, ProgramAccountsFilter, SolanaMirrorClient},
+    utils::{read_pubkey, read_u64},
+    Error,
+};
+
+/// Programs running the SPL stake pool code: the original deployment (jitoSOL, bSOL, ...)
+/// and the Sanctum single and multi validator deployments
+const STAKE_POOL_PROGRAM_IDS: [&str; 3] = [
+    "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy",
+    "SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY",
+    "SPMBzsVUuoHA4Jm6KunbsotaahvVikZs1JyTW6iJvbn",
+];
+
+/// `AccountType::StakePool`, validator lists share the program but start with a 2
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+/// `STAKE_POOL_ACCOUNT_TYPE` as the base58 bytes of a memcmp filter
+const STAKE_POOL_ACCOUNT_TYPE_BASE58: &str = "2";
+const STAKE_POOL_MINT_OFFSET: usize = 162;
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+const STAKE_POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+const MARINADE_STATE: &str = "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC";
+const MSOL_MINT: &str = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+/// SOL per mSOL, as a fixed point number with 32 fractional bits
+const MARINADE_MSOL_PRICE_OFFSET: usize = 512;
+
+/// Exchange rates only move once per epoch, when the pools update their balances
+const RATES_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Rates fetched while part of the sources failed are only kept for a short while,
+/// so an RPC outage doesn't turn into a refetch on every request
+const PARTIAL_RATES_TTL: Duration = Duration::from_secs(30);
+
+/// SOL per token of every known LST, keyed by mint
+pub type LstRates = HashMap<Pubkey, f64>;
+
+struct CachedRates {
+    rates: Arc<LstRates>,
+    expires_at: Instant,
+}
+
+static RATES_CACHE: Lazy<Mutex<Option<CachedRates>>> = Lazy::new(|| Mutex::new(None));
+
+/// Gets how much SOL a single token of every known LST redeems for, keyed by mint.
+/// Fetched once for the whole request, the lock isn't held while fetching
+pub async fn get_lst_rates(client: &SolanaMirrorClient) -> Arc<LstRates> {
+    if let Some(cached) = RATES_CACHE.lock().unwrap().as_ref() {
+        if cached.expires_at > Instant::now() {
+            return cached.rates.clone();
+        }
+    }
+
+    let (rates, complete) = fetch_lst_rates(client).await;
+    let rates = Arc::new(rates);
+    let ttl = if complete {
+        RATES_TTL
+    } else {
+        PARTIAL_RATES_TTL
+    };
+
+    *RATES_CACHE.lock().unwrap() = Some(CachedRates {
+        rates: rates.clone(),
+        expires_at: Instant::now() + ttl,
+    });
+
+    rates
+}
+
+/// Fetches the exchange rate of every known LST. Each source can fail on its own,
+/// the flag tells whether all of them answered
+async fn fetch_lst_rates(client: &SolanaMirrorClient) -> (LstRates, bool) {
+    let (stake_pools, msol_rate) =
+        join(get_stake_pool_rates(client), get_marinade_rate(client)).await;
+
+    let mut complete = true;
+    let mut rates = stake_pools.unwrap_or_else(|e| {
+        eprintln!("Failed to fetch the stake pool rates: {}", e);
+        complete = false;
+        HashMap::new()
+    });
+
+    match msol_rate {
+        Ok(msol_rate) => {
+            rates.insert(Pubkey::from_str(MSOL_MINT).unwrap(), msol_rate);
+        }
+        Err(e) => {
+            eprintln!("Failed to fetch the Marinade rate: {}", e);
+            complete = false;
+        }
+    }
+
+    (rates, complete)
+}
+
+async fn get_stake_pool_rates(client: &SolanaMirrorClient) -> Result<LstRates, Error> {
+    // Only the header is needed, it ends with the pool token supply
+    let data_slice = DataSlice {
+        offset: 0,
+        length: STAKE_POOL_TOKEN_SUPPLY_OFFSET + 8,
+    };
+
+    let program_ids: Vec<Pubkey> = STAKE_POOL_PROGRAM_IDS
+        .iter()
+        .map(|program_id| Pubkey::from_str(program_id).unwrap())
+        .collect();
+
+    // Only stake pools, not the validator lists of the same programs
+    let filters = vec![ProgramAccountsFilter::Memcmp(Memcmp {
+        offset: 0,
+        bytes: STAKE_POOL_ACCOUNT_TYPE_BASE58.to_string(),
+    })];
+
+    let futures = program_ids.iter().map(|program_id| {
+        client.get_program_accounts(program_id, filters.clone(), Some(data_slice.clone()))
+    });
+
+    let mut rates = HashMap::new();
+    for accounts in join_all(futures).await {
+        for account in accounts? {
+            let data = &account.data;
+            if data.first() != Some(&STAKE_POOL_ACCOUNT_TYPE) {
+                continue;
+            }
+
+            let (Some(mint), Some(total_lamports), Some(supply)) = (
+                read_pubkey(data, STAKE_POOL_MINT_OFFSET),
+                read_u64(data, STAKE_POOL_TOTAL_LAMPORTS_OFFSET),
+                read_u64(data, STAKE_POOL_TOKEN_SUPPLY_OFFSET),
+            ) else {
+                continue;
+            };
+
+            // Pools without tokens have no rate yet, they mint 1:1 on the first deposit
+            let rate = match supply {
+                0 => 1.0,
+                supply => total_lamports as f64 / supply as f64,
+            };
+            rates.insert(mint, rate);
+        }
+    }
+
+    Ok(rates)
+}
+
+async fn get_marinade_rate(client: &SolanaMirrorClient) -> Result<f64, Error> {
+    let state = Pubkey::from_str(MARINADE_STATE).unwrap();
+
+    let data = client
+        .get_account_info(
+            &state,
+            Some(GetAccountDataConfig {
+                commitment: None,
+                encoding: Some("base64".to_string()),
+            }),
+        )
+        .await?;
+
+    let msol_price = read_u64(&data, MARINADE_MSOL_PRICE_OFFSET).ok_or_else(|| {
+        Error::parse_error()
+            .with_source("marinade state too short")
+            .with_address(state)
+    })?;
+
+    Ok(msol_price as f64 / (1_u64 << 32) as f64)
+}
@@ -1,3 +1,248 @@
 pub mod accounts;
 pub mod dapps;
+pub mod lending;
+pub mod lst;
+pub mod stake;
 pub mod types;
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use futures::{
+    future::join3,
+    stream::{self, StreamExt},
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    client::SolanaMirrorClient,
+    price::{jupiter::JupiterQuoteSource, MAX_CONCURRENT_LOOKUPS},
+    Error, USDC_ADDRESS,
+};
+
+use accounts::get_parsed_accounts;
+use dapps::registry::DappRegistry;
+use lending::get_lending_obligations;
+use stake::get_stake_accounts;
+use types::{BalancesResponse, BalancesTotals, WalletBalances};
+
+/// Fetches every section of the balances of a wallet.
+/// Tokens that represent a DeFi position are only reported under `positions`
+pub async fn get_wallet_balances(
+    client: &SolanaMirrorClient,
+    registry: &DappRegistry,
+    pubkey: &Pubkey,
+    show_apps: bool,
+    hide_spam: bool,
+) -> Result<WalletBalances, Error> {
+    let (accounts, stake, lending) = join3(
+        get_parsed_accounts(client, pubkey),
+        get_stake_accounts(client, pubkey),
+        get_lending_obligations(client, pubkey),
+    )
+    .await;
+    let mut accounts = accounts?;
+
+    // Stake discovery scans the whole Stake program, which public RPCs often reject.
+    // It shouldn't take the token balances down with it
+    let stake = stake.unwrap_or_else(|e| {
+        eprintln!("Failed to fetch the stake accounts of {}: {}", pubkey, e);
+        Vec::new()
+    });
+
+    // Discovering positions by owner scans every protocol, it's only done if they're shown
+    let detected_positions = registry.detect(client, pubkey, &accounts, show_apps).await;
+
+    let mut positions = Vec::new();
+    let mut position_atas: Vec<String> = Vec::new();
+    if show_apps {
+        let results = registry.get_positions(client, &detected_positions).await;
+        for (detected, result) in detected_positions.iter().zip(results) {
+            match result {
+                Ok(position) => {
+                    positions.push(position);
+                    position_atas.extend(detected.token.map(|token| token.ata.clone()));
+                }
+                // The token stays listed as a plain token account
+                Err(e) => eprintln!(
+                    "Failed to fetch the {} position {}: {}",
+                    detected.adapter.id(),
+                    detected.account.pubkey,
+                    e
+                ),
+            }
+        }
+    } else {
+        position_atas.extend(
+            detected_positions
+                .iter()
+                .filter_map(|position| position.token.map(|token| token.ata.clone())),
+        );
+    }
+
+    accounts.retain(|account| !position_atas.contains(&account.ata));
+    if hide_spam {
+        accounts.retain(|account| !account.spam);
+    }
+
+    Ok(WalletBalances {
+        accounts,
+        stake,
+        positions,
+        lending,
+    })
+}
+
+/// Values the tokens at what selling their whole balance into USDC would return, keeping
+/// the market value aside. Tokens Jupiter can't route can't be sold, so they're worth 0.
+/// If the quote fails the token keeps its market value, without `exitLiquidity`.
+/// The other sections can't be sold as is and keep their market value
+pub async fn apply_liquidation_valuation(balances: &mut WalletBalances) {
+    let valuation_futures: Vec<_> = balances
+        .accounts
+        .iter_mut()
+        .filter(|account| account.mint != USDC_ADDRESS && account.balance.formatted > 0.0)
+        .map(|account| async move {
+            let (Ok(mint), Ok(amount)) = (
+                Pubkey::from_str(&account.mint),
+                account.balance.amount.parse::<u64>(),
+            ) else {
+                return;
+            };
+
+            account.market_value_usd = account.value_usd;
+            match JupiterQuoteSource.quote(&mint, amount).await {
+                Ok(Some(quote)) => {
+                    account.value_usd = Some(quote.value_usd);
+                    account.exit_liquidity = Some(quote.liquidity);
+                }
+                Ok(None) => account.value_usd = Some(0.0),
+                Err(e) => eprintln!("Failed to quote the liquidation of {}: {}", mint, e),
+            }
+        })
+        .collect();
+
+    stream::iter(valuation_futures)
+        .buffer_unordered(MAX_CONCURRENT_LOOKUPS)
+        .collect::<Vec<()>>()
+        .await;
+}
+
+/// Builds the response out of every section of the balances: computes the totals and
+/// the weight of each asset, drops the ones worth less than `min_usd` and sorts the rest by value.
+/// Totals are computed before dropping the dust, so they always reflect the whole wallet
+pub fn get_balances_response(balances: WalletBalances, min_usd: Option<f64>) -> BalancesResponse {
+    let totals = get_totals(&balances);
+
+    let WalletBalances {
+        mut accounts,
+        mut stake,
+        mut positions,
+        lending,
+    } = balances;
+
+    accounts
+        .iter_mut()
+        .for_each(|a| a.weight = get_weight(a.value_usd, &totals));
+    stake
+        .iter_mut()
+        .for_each(|s| s.weight = get_weight(s.value_usd, &totals));
+    positions
+        .iter_mut()
+        .for_each(|p| p.weight = get_weight(p.total_value_usd, &totals));
+
+    if let Some(min_usd) = min_usd {
+        accounts.retain(|a| !is_dust(a.value_usd, min_usd));
+        stake.retain(|s| !is_dust(s.value_usd, min_usd));
+        positions.retain(|p| !is_dust(p.total_value_usd, min_usd));
+    }
+
+    accounts.sort_by(|a, b| by_value_desc(a.value_usd, b.value_usd));
+    stake.sort_by(|a, b| by_value_desc(a.value_usd, b.value_usd));
+    positions.sort_by(|a, b| by_value_desc(a.total_value_usd, b.total_value_usd));
+
+    BalancesResponse {
+        totals,
+        accounts,
+        stake,
+        positions,
+        lending,
+    }
+}
+
+/// Sums the USD value of each section of the balances
+pub fn get_totals(balances: &WalletBalances) -> BalancesTotals {
+    let tokens: f64 = balances.accounts.iter().filter_map(|a| a.value_usd).sum();
+    let staked: f64 = balances.stake.iter().filter_map(|s| s.value_usd).sum();
+    let positions: f64 = balances
+        .positions
+        .iter()
+        .filter_map(|p| p.total_value_usd)
+        .sum();
+    let lending: f64 = balances.lending.iter().map(|l| l.net_value_usd).sum();
+    let supplied: f64 = balances.lending.iter().map(|l| l.supplied_usd).sum();
+
+    BalancesTotals {
+        tokens,
+        positions,
+        staked,
+        lending,
+        total: tokens + positions + staked + lending,
+        gross: tokens + positions + staked + supplied,
+    }
+}
+
+/// Share of the gross value held in an asset, `None` if either is unknown.
+/// The net total isn't used since borrows can bring it down to 0 or below
+pub(crate) fn get_weight(value: Option<f64>, totals: &BalancesTotals) -> Option<f64> {
+    if totals.gross > 0.0 {
+        value.map(|value| value / totals.gross)
+    } else {
+        None
+    }
+}
+
+/// Assets without a price aren't dust, their value is unknown rather than low.
+/// Spam, which is mostly unpriced, is hidden through `hideSpam` instead
+pub(crate) fn is_dust(value: Option<f64>, min_usd: f64) -> bool {
+    value.is_some_and(|value| value < min_usd)
+}
+
+/// Highest value first, assets without a price go last
+pub(crate) fn by_value_desc(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(tokens: f64, lending: f64, supplied: f64) -> BalancesTotals {
+        BalancesTotals {
+            tokens,
+            lending,
+            total: tokens + lending,
+            gross: tokens + supplied,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weighs_assets_against_the_gross_value() {
+        // 100 in tokens, 300 supplied and 350 borrowed: the net total is 50
+        let totals = totals(100.0, -50.0, 300.0);
+
+        assert_eq!(get_weight(Some(100.0), &totals), Some(0.25));
+        assert_eq!(get_weight(None, &totals), None);
+    }
+
+    #[test]
+    fn doesnt_weigh_assets_of_an_empty_wallet() {
+        assert_eq!(get_weight(Some(0.0), &totals(0.0, 0.0, 0.0)), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use futures::future::try_join3;
+use solana_program::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::{
+    pubkey::Pubkey,
+    stake::state::{Delegation, Meta, StakeStateV2},
+};
+
+use crate::{
+    client::{types::KeyedAccount, JsonRpcMethod, ProgramAccountsFilter, SolanaMirrorClient},
+    price::{get_prices_with_decimals, PriceQuote},
+    types::FormattedAmount,
+    Error, SOL_ADDRESS,
+};
+
+use types::{ParsedStakeAccount, StakeStatus};
+
+pub mod types;
+
+pub const STAKE_PROGRAM_ID: &str = "Stake11111111111111111111111111111111111111";
+
+/// Offsets of the authorized staker and withdrawer inside a stake account,
+/// right after the enum tag and the rent exempt reserve
+const STAKER_OFFSET: usize = 12;
+const WITHDRAWER_OFFSET: usize = 44;
+
+/// Fetches the stake accounts the given address can manage, either as staker or withdrawer
+pub async fn get_stake_accounts(
+    client: &SolanaMirrorClient,
+    address: &Pubkey,
+) -> Result<Vec<ParsedStakeAccount>, Error> {
+    let program_id = Pubkey::from_str(STAKE_PROGRAM_ID).unwrap();
+
+    let (staker_accounts, withdrawer_accounts, epoch_info) = try_join3(
+        client.get_program_accounts(
+            &program_id,
+            vec![ProgramAccountsFilter::memcmp(STAKER_OFFSET, address)],
+            None,
+        ),
+        client.get_program_accounts(
+            &program_id,
+            vec![ProgramAccountsFilter::memcmp(WITHDRAWER_OFFSET, address)],
+            None,
+        ),
+        client.get_epoch_info(None),
+    )
+    .await?;
+
+    // The address is usually both, so most accounts come back twice
+    let mut accounts = staker_accounts;
+    for account in withdrawer_accounts {
+        if !accounts.iter().any(|a| a.pubkey == account.pubkey) {
+            accounts.push(account);
+        }
+    }
+
+    if accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sol = Pubkey::from_str(SOL_ADDRESS).unwrap();
+    let quote = get_prices_with_decimals(client, &[(sol, 9)])
+        .await
+        .remove(&sol);
+
+    let mut parsed_accounts = Vec::new();
+    for account in &accounts {
+        if let Some(parsed) = parse_stake_account(account, epoch_info.epoch, quote.clone())? {
+            parsed_accounts.push(parsed);
+        }
+    }
+
+    Ok(parsed_accounts)
+}
+
+/// Parses the given stake account. Uninitialized accounts hold nothing worth reporting
+fn parse_stake_account(
+    account: &KeyedAccount,
+    current_epoch: u64,
+    quote: Option<PriceQuote>,
+) -> Result<Option<ParsedStakeAccount>, Error> {
+    let state: StakeStateV2 = bincode::deserialize(&account.data).map_err(|e| {
+        Error::parse_error()
+            .with_source(e)
+            .with_method(JsonRpcMethod::GetProgramAccounts)
+            .with_address(account.pubkey)
+    })?;
+
+    let (meta, delegation): (Meta, Option<Delegation>) = match state {
+        StakeStateV2::Initialized(meta) => (meta, None),
+        StakeStateV2::Stake(meta, stake, _) => (meta, Some(stake.delegation)),
+        StakeStateV2::Uninitialized | StakeStateV2::RewardsPool => return Ok(None),
+    };
+
+    let price = quote.as_ref().map(|quote| quote.price);
+
+    let status = match &delegation {
+        Some(delegation) => get_stake_status(delegation, current_epoch),
+        None => StakeStatus::Inactive,
+    };
+
+    let formatted = account.lamports as f64 / LAMPORTS_PER_SOL as f64;
+
+    Ok(Some(ParsedStakeAccount {
+        address: account.pubkey.to_string(),
+        status,
+        staker: meta.authorized.staker.to_string(),
+        withdrawer: meta.authorized.withdrawer.to_string(),
+        vote_account: delegation.map(|d| d.voter_pubkey.to_string()),
+        activation_epoch: delegation
+            .map(|d| d.activation_epoch)
+            .filter(|epoch| *epoch != u64::MAX),
+        deactivation_epoch: delegation
+            .map(|d| d.deactivation_epoch)
+            .filter(|epoch| *epoch != u64::MAX),
+        balance: FormattedAmount {
+            amount: account.lamports.to_string(),
+            formatted,
+        },
+        delegated_stake: delegation.map(|d| FormattedAmount {
+            amount: d.stake.to_string(),
+            formatted: d.stake as f64 / LAMPORTS_PER_SOL as f64,
+        }),
+        price,
+        value_usd: price.map(|price| formatted * price),
+        price_quote: quote,
+        weight: None,
+    }))
+}
+
+/// Derives the status from the delegation epochs. Warmup and cooldown are
+/// treated as lasting a single epoch, ignoring the network-wide rate limit
+pub fn get_stake_status(delegation: &Delegation, current_epoch: u64) -> StakeStatus {
+    // Bootstrap stakes are active since genesis and have `u64::MAX` as activation epoch
+    if delegation.deactivation_epoch != u64::MAX {
+        if current_epoch > delegation.deactivation_epoch {
+            StakeStatus::Inactive
+        } else {
+            StakeStatus::Deactivating
+        }
+    } else if delegation.activation_epoch == u64::MAX || current_epoch > delegation.activation_epoch
+    {
+        StakeStatus::Active
+    } else {
+        StakeStatus::Activating
+    }
+}
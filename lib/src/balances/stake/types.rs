@@ -0,0 +1,46 @@
+use serde::Serialize;
+
+use crate::{price::PriceQuote, types::FormattedAmount};
+
+/// Where a stake account is in its lifecycle, relative to the current epoch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum StakeStatus {
+    /// Delegated, waiting for the warmup to finish
+    #[serde(rename = "activating")]
+    Activating,
+    /// Delegated and earning rewards
+    #[serde(rename = "active")]
+    Active,
+    /// Deactivated, waiting for the cooldown to finish
+    #[serde(rename = "deactivating")]
+    Deactivating,
+    /// Not delegated, the lamports can be withdrawn
+    #[serde(rename = "inactive")]
+    Inactive,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParsedStakeAccount {
+    pub address: String,
+    pub status: StakeStatus,
+    pub staker: String,
+    pub withdrawer: String,
+    #[serde(rename = "voteAccount")]
+    pub vote_account: Option<String>,
+    #[serde(rename = "activationEpoch")]
+    pub activation_epoch: Option<u64>,
+    #[serde(rename = "deactivationEpoch")]
+    pub deactivation_epoch: Option<u64>,
+    /// Every lamport in the account, rent reserve included
+    pub balance: FormattedAmount,
+    /// Lamports delegated to the validator
+    #[serde(rename = "delegatedStake")]
+    pub delegated_stake: Option<FormattedAmount>,
+    pub price: Option<f64>,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+    #[serde(rename = "priceQuote", skip_serializing_if = "Option::is_none")]
+    pub price_quote: Option<PriceQuote>,
+    /// Share of the wallet gross value (`totals.gross`), between 0 and 1
+    pub weight: Option<f64>,
+}
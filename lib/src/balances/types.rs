@@ -1,8 +1,59 @@
-use super::{accounts::types::ParsedAta, dapps::types::ParsedPosition};
+use super::{
+    accounts::types::ParsedAta, dapps::types::ParsedPosition, lending::types::LendingObligation,
+    stake::types::ParsedStakeAccount,
+};
+
+/// How the tokens of a wallet are valued
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Valuation {
+    /// Balance times the price of a whole token
+    #[default]
+    Market,
+    /// What selling the whole balance into USDC would return, slippage included.
+    /// Tokens without a route to USDC are worth 0
+    Liquidation,
+}
+
+impl Valuation {
+    pub fn new(valuation: &str) -> Option<Self> {
+        match valuation.to_lowercase().as_str() {
+            "market" => Some(Self::Market),
+            "liquidation" => Some(Self::Liquidation),
+            _ => None,
+        }
+    }
+}
+
+/// Every section of the balances of a wallet, before the totals are computed
+pub struct WalletBalances {
+    pub accounts: Vec<ParsedAta>,
+    pub stake: Vec<ParsedStakeAccount>,
+    pub positions: Vec<ParsedPosition>,
+    pub lending: Vec<LendingObligation>,
+}
 
 #[derive(serde::Serialize)]
 pub struct BalancesResponse {
+    pub totals: BalancesTotals,
     pub accounts: Vec<ParsedAta>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub raydium: Option<Vec<ParsedPosition>>,
+    pub stake: Vec<ParsedStakeAccount>,
+    /// DeFi positions of every protocol, told apart by `protocol.id`
+    pub positions: Vec<ParsedPosition>,
+    /// Deposits and borrows in lending markets, one entry per obligation.
+    /// Obligations have no weight, their deposits only count toward `totals.gross`
+    pub lending: Vec<LendingObligation>,
+}
+
+/// USD value of each section of the response, dust included
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct BalancesTotals {
+    pub tokens: f64,
+    pub positions: f64,
+    pub staked: f64,
+    /// Supplied minus borrowed value over every lending obligation
+    pub lending: f64,
+    pub total: f64,
+    /// Value of every asset held, lending deposits included and borrows left out.
+    /// Weights are shares of it, so debt can't push them past 1
+    pub gross: f64,
 }
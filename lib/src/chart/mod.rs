@@ -1,3 +1,4 @@
+use futures::future::try_join_all;
 use solana_sdk::pubkey::Pubkey;
 use std::{
     collections::{HashMap, HashSet},
@@ -9,10 +10,10 @@ use types::{ChartData, ChartDataWithPrice, GetCoinMarketChartParams};
 use crate::{
     client::SolanaMirrorClient,
     coingecko::{get_coingecko_id, CoingeckoClient},
-    price::get_price,
+    price::get_prices,
     transactions::{get_parsed_transactions, types::ParsedTransaction},
-    types::FormattedAmountWithPrice,
-    Error, SOL_ADDRESS,
+    types::{FormattedAmount, FormattedAmountWithPrice},
+    Error,
 };
 
 #[derive(Debug)]
@@ -62,6 +63,77 @@ pub async fn get_chart_data(
     Ok(price_states)
 }
 
+/// Same as `get_chart_data`, with the balances of every wallet added together
+pub async fn get_portfolio_chart_data(
+    client: &SolanaMirrorClient,
+    coingecko_client: &CoingeckoClient,
+    pubkeys: &[Pubkey],
+    timeframe: Timeframe,
+    range: u8,
+) -> Result<Vec<ChartDataWithPrice>, Error> {
+    let wallet_txs = try_join_all(
+        pubkeys
+            .iter()
+            .map(|pubkey| get_parsed_transactions(client, pubkey, None)),
+    )
+    .await?;
+
+    let wallet_states: Vec<Vec<ChartData>> = wallet_txs
+        .iter()
+        .map(|txs| get_balance_states(&txs.transactions))
+        .collect();
+    let states = merge_balance_states(&wallet_states);
+    let filtered_states = filter_balance_states(&states, timeframe, range);
+    let price_states = get_price_states(client, coingecko_client, &filtered_states).await?;
+
+    Ok(price_states)
+}
+
+/// Puts the states of several wallets on a common timeline. At every timestamp in which
+/// any of the wallets changed, each wallet contributes the last state it had by then
+fn merge_balance_states(wallet_states: &[Vec<ChartData>]) -> Vec<ChartData> {
+    let mut timestamps: Vec<i64> = wallet_states
+        .iter()
+        .flatten()
+        .map(|state| state.timestamp)
+        .collect();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let mut cursors = vec![0; wallet_states.len()];
+    let mut merged_states = Vec::with_capacity(timestamps.len());
+
+    for timestamp in timestamps {
+        let mut balances: HashMap<String, FormattedAmount> = HashMap::new();
+
+        for (states, cursor) in wallet_states.iter().zip(cursors.iter_mut()) {
+            while *cursor < states.len() && states[*cursor].timestamp <= timestamp {
+                *cursor += 1;
+            }
+
+            // The wallet had no transactions yet
+            let Some(state) = cursor.checked_sub(1).map(|idx| &states[idx]) else {
+                continue;
+            };
+
+            for (mint, balance) in &state.balances {
+                let merged = balances.entry(mint.clone()).or_default();
+                let amount = merged.amount.parse::<u128>().unwrap_or_default()
+                    + balance.amount.parse::<u128>().unwrap_or_default();
+                merged.amount = amount.to_string();
+                merged.formatted += balance.formatted;
+            }
+        }
+
+        merged_states.push(ChartData {
+            timestamp,
+            balances,
+        });
+    }
+
+    merged_states
+}
+
 /// Creates a series of states with the balances of a wallet at each transaction
 fn get_balance_states(txs: &Vec<ParsedTransaction>) -> Vec<ChartData> {
     let mut states: Vec<ChartData> = Vec::with_capacity(txs.len());
@@ -90,11 +162,7 @@ fn get_balance_states(txs: &Vec<ParsedTransaction>) -> Vec<ChartData> {
     states
 }
 
-fn filter_balance_states(
-    states: &Vec<ChartData>,
-    timeframe: Timeframe,
-    range: u8,
-) -> Vec<ChartData> {
+fn filter_balance_states(states: &[ChartData], timeframe: Timeframe, range: u8) -> Vec<ChartData> {
     if states.is_empty() {
         return Vec::new();
     }
@@ -161,7 +229,7 @@ fn filter_balance_states(
 pub async fn get_price_states(
     client: &SolanaMirrorClient,
     coingecko_client: &CoingeckoClient,
-    states: &Vec<ChartData>,
+    states: &[ChartData],
 ) -> Result<Vec<ChartDataWithPrice>, Error> {
     let mut coingecko_prices: HashMap<String, Vec<(u64, f64)>> = HashMap::new();
 
@@ -197,20 +265,31 @@ pub async fn get_price_states(
         }
     }
 
+    if states.is_empty() {
+        return Ok(Vec::new());
+    }
+
     let mut new_states: Vec<ChartDataWithPrice> = Vec::with_capacity(states.len());
     let last_state_index = states.len() - 1;
 
-    for (i, state) in states.into_iter().enumerate() {
+    // Current prices for the last state, looked up in a single batch
+    let last_mints: Vec<Pubkey> = states[last_state_index]
+        .balances
+        .keys()
+        .map(|mint| Pubkey::from_str(mint).unwrap())
+        .collect();
+    let current_prices = get_prices(client, &last_mints).await;
+
+    for (i, state) in states.iter().enumerate() {
         let timestamp = state.timestamp;
         let mut bals_with_price = HashMap::new();
 
         for (mint, balance) in &state.balances {
             let price = if i == last_state_index {
-                // Get current price from Jup for accurracy
-                let decimals = if mint == SOL_ADDRESS { Some(9) } else { None };
-                get_price(client, Pubkey::from_str(mint).unwrap(), decimals)
-                    .await
-                    .unwrap_or(0.0)
+                // Get current price for accurracy
+                current_prices
+                    .get(&Pubkey::from_str(mint).unwrap())
+                    .map_or(0.0, |quote| quote.price)
             } else {
                 // Get the right index from the Coingecko prices
                 let index = ((timestamp - from) / time_step) as usize;
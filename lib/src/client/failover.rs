@@ -0,0 +1,249 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::Error;
+
+use super::check_rate_limit;
+use super::retry::is_retryable;
+use super::transport::{HttpTransport, RpcTransport};
+
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub url: String,
+    /// Share of the traffic the endpoint receives relative to the others
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Cycles through the endpoints proportionally to their weight
+    RoundRobin,
+    /// Prefers the endpoint with the fewest recent failures and the lowest latency
+    Health,
+}
+
+impl Strategy {
+    pub fn new(strategy: &str) -> Option<Self> {
+        match strategy.to_lowercase().as_str() {
+            "round-robin" => Some(Self::RoundRobin),
+            "health" => Some(Self::Health),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FailoverConfig {
+    /// Consecutive failures (or rate limits) after which an endpoint is ejected
+    pub max_failures: u32,
+    /// How long an ejected endpoint is skipped before it gets probed again
+    pub cooldown: Duration,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    ejected_until: Option<Instant>,
+    /// Exponentially weighted average of the response time, in milliseconds
+    latency_ms: f64,
+}
+
+struct EndpointState {
+    transport: Box<dyn RpcTransport>,
+    weight: u32,
+    health: Mutex<Health>,
+}
+
+impl EndpointState {
+    fn is_available(&self, now: Instant) -> bool {
+        let health = self.health.lock().unwrap();
+        health.ejected_until.is_none_or(|until| until <= now)
+    }
+
+    fn record_success(&self, elapsed: Duration) {
+        let mut health = self.health.lock().unwrap();
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+
+        health.consecutive_failures = 0;
+        health.ejected_until = None;
+        health.latency_ms = if health.latency_ms == 0.0 {
+            elapsed_ms
+        } else {
+            0.8 * health.latency_ms + 0.2 * elapsed_ms
+        };
+    }
+
+    /// A failed probe re-ejects the endpoint right away, since its counter
+    /// is only reset by a successful response
+    fn record_failure(&self, config: &FailoverConfig) {
+        let mut health = self.health.lock().unwrap();
+
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= config.max_failures {
+            health.ejected_until = Some(Instant::now() + config.cooldown);
+        }
+    }
+}
+
+/// Spreads requests over several RPC endpoints, ejecting the ones that keep
+/// failing and probing them again once their cooldown is over
+pub struct FailoverTransport {
+    endpoints: Vec<EndpointState>,
+    /// Endpoint indexes repeated by weight, walked by the round robin cursor
+    schedule: Vec<usize>,
+    cursor: AtomicUsize,
+    strategy: Strategy,
+    config: FailoverConfig,
+}
+
+impl FailoverTransport {
+    pub fn new(endpoints: Vec<Endpoint>, strategy: Strategy) -> Self {
+        let inner_client = Client::new();
+        let transports = endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let transport: Box<dyn RpcTransport> =
+                    Box::new(HttpTransport::from_client(&inner_client, endpoint.url));
+                (transport, endpoint.weight)
+            })
+            .collect();
+
+        Self::from_transports(transports, strategy)
+    }
+
+    /// Balances over arbitrary transports paired with their weight
+    pub fn from_transports(
+        transports: Vec<(Box<dyn RpcTransport>, u32)>,
+        strategy: Strategy,
+    ) -> Self {
+        let endpoints: Vec<EndpointState> = transports
+            .into_iter()
+            .map(|(transport, weight)| EndpointState {
+                transport,
+                weight: weight.max(1),
+                health: Mutex::new(Health::default()),
+            })
+            .collect();
+
+        let schedule = endpoints
+            .iter()
+            .enumerate()
+            .flat_map(|(i, endpoint)| std::iter::repeat_n(i, endpoint.weight as usize))
+            .collect();
+
+        Self {
+            endpoints,
+            schedule,
+            cursor: AtomicUsize::new(0),
+            strategy,
+            config: FailoverConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: FailoverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Orders the endpoints in which a request should be attempted
+    fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+
+        let mut order: Vec<usize> = match self.strategy {
+            Strategy::RoundRobin => {
+                let start = self.cursor.fetch_add(1, Ordering::Relaxed);
+                let mut order = Vec::with_capacity(self.endpoints.len());
+                for offset in 0..self.schedule.len() {
+                    let idx = self.schedule[(start + offset) % self.schedule.len()];
+                    if !order.contains(&idx) {
+                        order.push(idx);
+                    }
+                }
+                order
+            }
+            Strategy::Health => {
+                let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+                order.sort_by(|a, b| {
+                    let score = |idx: &usize| {
+                        let endpoint = &self.endpoints[*idx];
+                        let health = endpoint.health.lock().unwrap();
+                        (
+                            health.consecutive_failures,
+                            health.latency_ms / endpoint.weight as f64,
+                        )
+                    };
+                    let (a, b) = (score(a), score(b));
+                    // Total order, a NaN latency can't make the sort panic
+                    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+                });
+                order
+            }
+        };
+
+        let available: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|idx| self.endpoints[*idx].is_available(now))
+            .collect();
+
+        if !available.is_empty() {
+            return available;
+        }
+
+        // Every endpoint is ejected, try the one that is closest to being probed again
+        order.sort_by_key(|idx| self.endpoints[*idx].health.lock().unwrap().ejected_until);
+        order.truncate(1);
+        order
+    }
+}
+
+#[rocket::async_trait]
+impl RpcTransport for FailoverTransport {
+    async fn send(&self, body: &Value) -> Result<Value, Error> {
+        let mut last_err = Error::fetch_error().with_source("no RPC endpoint configured");
+
+        for idx in self.candidates() {
+            let endpoint = &self.endpoints[idx];
+            let started = Instant::now();
+
+            let res = endpoint.transport.send(body).await.and_then(|res| {
+                check_rate_limit(&res)?;
+                Ok(res)
+            });
+
+            match res {
+                Ok(res) => {
+                    endpoint.record_success(started.elapsed());
+                    return Ok(res);
+                }
+                Err(e) => {
+                    if !is_retryable(&e) {
+                        return Err(e);
+                    }
+                    endpoint.record_failure(&self.config);
+                    last_err = e;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
+    /// Url of the first configured endpoint
+    fn rpc_url(&self) -> Option<&str> {
+        self.endpoints.first()?.transport.rpc_url()
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rocket::tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::sleep,
+};
+
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    /// Sustained rate at which requests are let through, 0 or less doesn't limit the rate
+    pub requests_per_second: f64,
+    /// Requests that can go out at once after a quiet period
+    pub burst: u32,
+    /// Requests that can be waiting on a response at the same time
+    pub max_in_flight: usize,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 10.0,
+            burst: 20,
+            max_in_flight: 16,
+        }
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket paired with a semaphore, shared by every request of a client
+pub struct RateLimiter {
+    limit: RateLimit,
+    /// `None` if only the requests in flight are limited
+    bucket: Option<Mutex<TokenBucket>>,
+    in_flight: Semaphore,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let bucket = (limit.requests_per_second > 0.0 && limit.requests_per_second.is_finite())
+            .then(|| {
+                Mutex::new(TokenBucket {
+                    tokens: limit.burst as f64,
+                    last_refill: Instant::now(),
+                })
+            });
+
+        Self {
+            bucket,
+            in_flight: Semaphore::new(limit.max_in_flight.max(1)),
+            limit,
+        }
+    }
+
+    /// Waits for a free slot and a token. The slot is released when the permit is dropped
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.in_flight.acquire().await.unwrap();
+
+        let wait = self.reserve_token();
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+
+        permit
+    }
+
+    /// Takes a token, letting the balance go negative so concurrent callers
+    /// queue up behind each other instead of all waking up at once
+    fn reserve_token(&self) -> Duration {
+        let Some(bucket) = &self.bucket else {
+            return Duration::ZERO;
+        };
+        let mut bucket = bucket.lock().unwrap();
+        let now = Instant::now();
+        let rate = self.limit.requests_per_second;
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(self.limit.burst as f64);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            // A tiny rate can ask for a wait longer than a `Duration` holds
+            Duration::try_from_secs_f64(-bucket.tokens / rate).unwrap_or(Duration::MAX)
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimit::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(requests_per_second: f64, burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimit {
+            requests_per_second,
+            burst,
+            max_in_flight: 4,
+        })
+    }
+
+    #[test]
+    fn lets_the_burst_through_then_spaces_requests() {
+        let limiter = limiter(10.0, 2);
+
+        assert_eq!(limiter.reserve_token(), Duration::ZERO);
+        assert_eq!(limiter.reserve_token(), Duration::ZERO);
+
+        let wait = limiter.reserve_token();
+        assert!(wait > Duration::from_millis(50) && wait <= Duration::from_millis(100));
+        // Callers queue up behind each other
+        assert!(limiter.reserve_token() > wait);
+    }
+
+    #[test]
+    fn doesnt_limit_the_rate_without_a_positive_one() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limiter = limiter(rate, 0);
+            for _ in 0..100 {
+                assert_eq!(limiter.reserve_token(), Duration::ZERO);
+            }
+        }
+    }
+
+    #[test]
+    fn caps_the_wait_of_a_tiny_rate() {
+        let limiter = limiter(f64::MIN_POSITIVE, 0);
+        assert_eq!(limiter.reserve_token(), Duration::MAX);
+    }
+
+    #[rocket::async_test]
+    async fn limits_the_requests_in_flight() {
+        let limiter = limiter(0.0, 0);
+
+        let permits: Vec<_> = futures::future::join_all((0..4).map(|_| limiter.acquire())).await;
+        assert_eq!(limiter.in_flight.available_permits(), 0);
+
+        drop(permits);
+        assert_eq!(limiter.in_flight.available_permits(), 4);
+    }
+}
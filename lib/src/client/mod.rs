@@ -1,21 +1,32 @@
-use std::future::Future;
-use std::pin::Pin;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
 
 use crate::Error;
 use base64::Engine;
+use failover::{Endpoint, FailoverTransport, Strategy};
+use futures::future::try_join_all;
+use limiter::{RateLimit, RateLimiter};
 use reqwest::Client;
+use retry::{retry, RetryPolicy};
 use serde::{self, de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{from_value, Value};
 use solana_sdk::pubkey::Pubkey;
+use transport::{HttpTransport, RpcTransport};
 use types::{
-    AccountDataResultData, AccountsResultData, BalanceResultData, DecimalsResultData, Signature,
-    Transaction,
+    AccountDataResultData, AccountMetadataInfo, AccountsResultData, BalanceResultData,
+    DecimalsResultData, EpochInfo, KeyedAccount, MintInfo, MintResultData,
+    MultipleAccountsResultData, ProgramAccount, Signature, Transaction,
 };
 use uuid::Uuid;
 
+pub mod failover;
+pub mod limiter;
+pub mod retry;
+pub mod transport;
 pub mod types;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy)]
 pub enum JsonRpcMethod {
     GetTokenAccountsByOwner,
     GetBalance,
@@ -23,18 +34,25 @@ pub enum JsonRpcMethod {
     GetDecimals,
     GetTransaction,
     GetSignaturesForAddress,
+    GetProgramAccounts,
+    GetEpochInfo,
+    GetMultipleAccounts,
 }
 
-impl JsonRpcMethod {
-    pub fn to_string(&self) -> String {
-        match self {
-            JsonRpcMethod::GetTokenAccountsByOwner => "getTokenAccountsByOwner".to_string(),
-            JsonRpcMethod::GetBalance => "getBalance".to_string(),
-            JsonRpcMethod::GetAccountInfo => "getAccountInfo".to_string(),
-            JsonRpcMethod::GetDecimals => "getTokenSupply".to_string(),
-            JsonRpcMethod::GetTransaction => "getTransaction".to_string(),
-            JsonRpcMethod::GetSignaturesForAddress => "getSignaturesForAddress".to_string(),
-        }
+impl fmt::Display for JsonRpcMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let method = match self {
+            JsonRpcMethod::GetTokenAccountsByOwner => "getTokenAccountsByOwner",
+            JsonRpcMethod::GetBalance => "getBalance",
+            JsonRpcMethod::GetAccountInfo => "getAccountInfo",
+            JsonRpcMethod::GetDecimals => "getTokenSupply",
+            JsonRpcMethod::GetTransaction => "getTransaction",
+            JsonRpcMethod::GetSignaturesForAddress => "getSignaturesForAddress",
+            JsonRpcMethod::GetProgramAccounts => "getProgramAccounts",
+            JsonRpcMethod::GetEpochInfo => "getEpochInfo",
+            JsonRpcMethod::GetMultipleAccounts => "getMultipleAccounts",
+        };
+        write!(f, "{}", method)
     }
 }
 
@@ -62,7 +80,7 @@ pub struct JsonRpcError {
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ErrorDetails {
-    code: i32,
+    code: i64,
     message: String,
 }
 
@@ -120,6 +138,65 @@ pub struct GetAccountDataConfig {
 
 pub type GetAccountDataParams = (String, Option<GetAccountDataConfig>);
 
+// get_multiple_accounts
+pub type GetMultipleAccountsResponse = JsonRpcResponse<MultipleAccountsResultData>;
+
+pub type GetMultipleAccountsParams = (Vec<String>, Option<GetAccountDataConfig>);
+
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// get_mint
+pub type GetMintResponse = JsonRpcResponse<MintResultData>;
+
+// get_program_accounts
+pub type GetProgramAccountsResponse = JsonRpcResponse<Vec<ProgramAccount>>;
+
+/// Narrows down the accounts returned by `getProgramAccounts`
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ProgramAccountsFilter {
+    #[serde(rename = "memcmp")]
+    Memcmp(Memcmp),
+    #[serde(rename = "dataSize")]
+    DataSize(u64),
+}
+
+/// Matches accounts whose data contains `bytes` (base58) at `offset`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Memcmp {
+    pub offset: usize,
+    pub bytes: String,
+}
+
+impl ProgramAccountsFilter {
+    pub fn memcmp(offset: usize, pubkey: &Pubkey) -> Self {
+        Self::Memcmp(Memcmp {
+            offset,
+            bytes: pubkey.to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GetProgramAccountsConfig {
+    pub commitment: Option<String>,
+    pub encoding: Option<String>,
+    #[serde(rename = "dataSlice")]
+    pub data_slice: Option<DataSlice>,
+    pub filters: Option<Vec<ProgramAccountsFilter>>,
+}
+
+pub type GetProgramAccountsParams = (String, Option<GetProgramAccountsConfig>);
+
+// get_epoch_info
+pub type GetEpochInfoResponse = JsonRpcResponse<EpochInfo>;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetEpochInfoConfig {
+    pub commitment: Option<String>,
+}
+
+pub type GetEpochInfoParams = (Option<GetEpochInfoConfig>,);
+
 // get_decimals
 pub type GetDecimalsResponse = JsonRpcResponse<DecimalsResultData>;
 
@@ -156,92 +233,126 @@ pub struct GetTransactionConfig {
 
 pub type GetTransactionParams = (String, Option<GetTransactionConfig>);
 
-// TODO: implement on all methods
-#[allow(dead_code)]
-async fn retry<T, F>(callback: F, max_retries: u8) -> Result<T, Error>
-where
-    F: Fn() -> Pin<Box<dyn Future<Output = Result<T, Error>>>>,
-{
-    for attempt in 0..=max_retries {
-        match callback().await {
-            Ok(res) => return Ok(res),
-            Err(e) => {
-                if attempt == max_retries {
-                    return Err(e);
-                } else {
-                    match e {
-                        Error::TooManyRequests => continue,
-                        _ => return Err(e),
-                    }
-                }
-            }
-        }
+/// Rejects responses in which the node reports a rate limit, either for
+/// the whole payload or for any request inside a batch
+pub(crate) fn check_rate_limit(res: &Value) -> Result<(), Error> {
+    let is_rate_limited = |res: &Value| res["error"]["code"].as_i64() == Some(429);
+
+    let rate_limited = match res {
+        Value::Array(responses) => responses.iter().any(is_rate_limited),
+        res => is_rate_limited(res),
+    };
+
+    if rate_limited {
+        return Err(Error::too_many_requests(None));
     }
 
-    Err(Error::FetchError)
+    Ok(())
+}
+
+/// Decodes the base64 data of an account returned by the node
+fn decode_account(
+    pubkey: Pubkey,
+    account: AccountMetadataInfo,
+    method: JsonRpcMethod,
+) -> Result<KeyedAccount, Error> {
+    let data = base64::prelude::BASE64_STANDARD
+        .decode(&account.data[0])
+        .map_err(|e| {
+            Error::parse_error()
+                .with_source(e)
+                .with_method(method)
+                .with_address(pubkey)
+        })?;
+
+    Ok(KeyedAccount {
+        pubkey,
+        lamports: account.lamports,
+        owner: account.owner,
+        data,
+    })
 }
 
 fn deserialize<T: DeserializeOwned>(res: &Value) -> Result<T, Error> {
     // Try deserializing into an error first
     if let Ok(deserialized_err) = from_value::<JsonRpcError>(res.clone()) {
-        match deserialized_err.error.code {
-            429 => return Err(Error::TooManyRequests),
-            _ => return Err(Error::FetchError),
-        }
+        let ErrorDetails { code, message } = deserialized_err.error;
+        return match code {
+            429 => Err(Error::too_many_requests(None)),
+            _ => Err(Error::rpc_error(code, message)),
+        };
     }
 
-    match from_value::<T>(res.clone()) {
-        Ok(res) => Ok(res),
-        Err(_) => Err(Error::ParseError),
-    }
+    from_value::<T>(res.clone()).map_err(|e| Error::parse_error().with_source(e))
 }
 
 pub struct SolanaMirrorClient {
-    inner_client: Client,
-    pub rpc_url: String,
+    transport: Arc<dyn RpcTransport>,
+    retry_policy: RetryPolicy,
+    limiter: RateLimiter,
 }
 
 impl SolanaMirrorClient {
     pub fn new(rpc_url: String) -> Self {
-        Self {
-            inner_client: Client::new(),
-            rpc_url,
-        }
+        Self::from_transport(HttpTransport::new(rpc_url))
     }
 
     pub fn from_client(inner_client: &Client, rpc_url: String) -> Self {
+        Self::from_transport(HttpTransport::from_client(inner_client, rpc_url))
+    }
+
+    /// Creates a client that balances its requests over several RPC endpoints
+    pub fn from_endpoints(endpoints: Vec<Endpoint>, strategy: Strategy) -> Self {
+        Self::from_transport(FailoverTransport::new(endpoints, strategy))
+    }
+
+    /// Creates a client that sends its requests through the given transport (eg. a `FixtureTransport` in tests)
+    pub fn from_transport<T: RpcTransport + 'static>(transport: T) -> Self {
         Self {
-            inner_client: inner_client.clone(),
-            rpc_url,
+            transport: Arc::new(transport),
+            retry_policy: RetryPolicy::default(),
+            limiter: RateLimiter::default(),
         }
     }
 
+    /// Url of the node the client talks to. It replaces the `rpc_url` field the client had
+    /// before it sent its requests through a transport, and is `None` for transports without one
+    pub fn rpc_url(&self) -> Option<&str> {
+        self.transport.rpc_url()
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.limiter = RateLimiter::new(rate_limit);
+        self
+    }
+
+    /// Sends the payload, retrying transient failures according to the retry policy.
+    /// Every attempt goes through the rate limiter
+    async fn send(&self, body: &Value) -> Result<Value, Error> {
+        retry(&self.retry_policy, || async {
+            let _permit = self.limiter.acquire().await;
+            let res = self.transport.send(body).await?;
+            check_rate_limit(&res)?;
+            Ok(res)
+        })
+        .await
+    }
+
     async fn make_batch_request<T: Serialize>(
         &self,
-        body: &Vec<JsonRpcRequest<T>>,
+        body: &[JsonRpcRequest<T>],
     ) -> Result<Value, Error> {
-        let serialized = match serde_json::to_string(body) {
+        let serialized = match serde_json::to_value(body) {
             Ok(serialized) => serialized,
-            Err(_) => return Err(Error::ParseError),
+            Err(e) => return Err(Error::parse_error().with_source(e)),
         };
 
-        let req = self
-            .inner_client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .header("solana-client", "js/0.0.0-development")
-            .body(serialized);
-
-        match req.send().await {
-            Ok(response) => {
-                let res = response
-                    .json::<Value>()
-                    .await
-                    .map_err(|_| Error::ParseError)?;
-                Ok(res)
-            }
-            Err(_) => Err(Error::FetchError),
-        }
+        self.send(&serialized).await
     }
 
     async fn make_request<T: Serialize>(
@@ -256,28 +367,28 @@ impl SolanaMirrorClient {
             id: Uuid::new_v4().to_string(),
         };
 
-        let serialized = match serde_json::to_string(body) {
+        let serialized = match serde_json::to_value(body) {
             Ok(serialized) => serialized,
-            Err(_) => return Err(Error::ParseError),
+            Err(e) => return Err(Error::parse_error().with_source(e)),
         };
 
-        let req = self
-            .inner_client
-            .post(&self.rpc_url)
-            .header("Content-Type", "application/json")
-            .header("solana-client", "js/0.0.0-development")
-            .body(serialized);
-
-        match req.send().await {
-            Ok(response) => {
-                let res = response
-                    .json::<Value>()
-                    .await
-                    .map_err(|_| Error::ParseError)?;
-                Ok(res)
-            }
-            Err(_) => Err(Error::FetchError),
-        }
+        self.send(&serialized)
+            .await
+            .map_err(|e| e.with_method(method))
+    }
+
+    /// Sends a single request and deserializes its response,
+    /// tagging any error with the method and the address it was about
+    async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: JsonRpcMethod,
+        address: &str,
+        params: P,
+    ) -> Result<R, Error> {
+        self.make_request(method, Some(params))
+            .await
+            .and_then(|res| deserialize::<R>(&res))
+            .map_err(|e| e.with_method(method).with_address(address))
     }
 
     pub async fn get_token_accounts_by_owner(
@@ -288,11 +399,12 @@ impl SolanaMirrorClient {
     ) -> Result<GetTokenAccountsByOwnerResponse, Error> {
         let params: GetTokenAccountsByOwnerParams = (owner.to_string(), filter, config);
 
-        let res = self
-            .make_request(JsonRpcMethod::GetTokenAccountsByOwner, Some(params))
-            .await?;
-
-        deserialize::<GetTokenAccountsByOwnerResponse>(&res)
+        self.call(
+            JsonRpcMethod::GetTokenAccountsByOwner,
+            &owner.to_string(),
+            params,
+        )
+        .await
     }
 
     pub async fn get_balance(
@@ -302,12 +414,15 @@ impl SolanaMirrorClient {
     ) -> Result<u64, Error> {
         let params: GetBalanceParams = (owner.to_string(), config);
 
-        let res = self
-            .make_request(JsonRpcMethod::GetBalance, Some(params))
+        let bal: GetBalanceResponse = self
+            .call(JsonRpcMethod::GetBalance, &owner.to_string(), params)
             .await?;
-        match deserialize::<GetBalanceResponse>(&res) {
-            Ok(bal) => Ok(bal.result.unwrap().value),
-            Err(e) => Err(e),
+
+        match bal.result {
+            Some(result) => Ok(result.value),
+            None => Err(Error::parse_error()
+                .with_method(JsonRpcMethod::GetBalance)
+                .with_address(owner)),
         }
     }
 
@@ -318,22 +433,139 @@ impl SolanaMirrorClient {
     ) -> Result<Vec<u8>, Error> {
         let params: GetAccountDataParams = (pubkey.to_string(), config);
 
-        let res = self
-            .make_request(JsonRpcMethod::GetAccountInfo, Some(params))
+        let acc: GetAccountDataResponse = self
+            .call(JsonRpcMethod::GetAccountInfo, &pubkey.to_string(), params)
             .await?;
-        match deserialize::<GetAccountDataResponse>(&res) {
-            Ok(acc) => {
-                let base64_data = &acc.result.value.data[0];
-                let decoded_data = base64::prelude::BASE64_STANDARD
-                    .decode(base64_data)
-                    .expect("Failed to decode base64 data.");
-
-                Ok(decoded_data)
-            }
-            Err(e) => Err(e),
+
+        let base64_data = &acc.result.value.data[0];
+        base64::prelude::BASE64_STANDARD
+            .decode(base64_data)
+            .map_err(|e| {
+                Error::parse_error()
+                    .with_source(e)
+                    .with_method(JsonRpcMethod::GetAccountInfo)
+                    .with_address(pubkey)
+            })
+    }
+
+    /// Fetches a mint account parsed by the node, Token-2022 extensions included
+    pub async fn get_mint(&self, mint: &Pubkey) -> Result<MintInfo, Error> {
+        let params: GetAccountDataParams = (
+            mint.to_string(),
+            Some(GetAccountDataConfig {
+                commitment: None,
+                encoding: Some("jsonParsed".to_string()),
+            }),
+        );
+
+        let res: GetMintResponse = self
+            .call(JsonRpcMethod::GetAccountInfo, &mint.to_string(), params)
+            .await?;
+
+        match res.result.value {
+            Some(account) => Ok(account.data.parsed.info),
+            None => Err(Error::parse_error()
+                .with_source("mint account not found")
+                .with_method(JsonRpcMethod::GetAccountInfo)
+                .with_address(mint)),
         }
     }
 
+    /// Fetches the accounts owned by a program that match every filter.
+    /// The data is always requested as base64 and returned decoded
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<ProgramAccountsFilter>,
+        data_slice: Option<DataSlice>,
+    ) -> Result<Vec<KeyedAccount>, Error> {
+        let params: GetProgramAccountsParams = (
+            program_id.to_string(),
+            Some(GetProgramAccountsConfig {
+                commitment: None,
+                encoding: Some("base64".to_string()),
+                data_slice,
+                filters: Some(filters),
+            }),
+        );
+
+        let res: GetProgramAccountsResponse = self
+            .call(
+                JsonRpcMethod::GetProgramAccounts,
+                &program_id.to_string(),
+                params,
+            )
+            .await?;
+
+        res.result
+            .into_iter()
+            .map(|account| {
+                let pubkey = Pubkey::from_str(&account.pubkey).map_err(|e| {
+                    Error::parse_error()
+                        .with_source(e)
+                        .with_method(JsonRpcMethod::GetProgramAccounts)
+                        .with_address(&account.pubkey)
+                })?;
+                decode_account(pubkey, account.account, JsonRpcMethod::GetProgramAccounts)
+            })
+            .collect()
+    }
+
+    /// Fetches several accounts at once, in chunks of 100 (the most a node accepts).
+    /// Accounts that don't exist come back as `None`, in the same position as their key
+    pub async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<KeyedAccount>>, Error> {
+        let chunk_futures = pubkeys
+            .chunks(MAX_MULTIPLE_ACCOUNTS)
+            .map(|chunk| async move {
+                let params: GetMultipleAccountsParams = (
+                    chunk.iter().map(|pubkey| pubkey.to_string()).collect(),
+                    Some(GetAccountDataConfig {
+                        commitment: None,
+                        encoding: Some("base64".to_string()),
+                    }),
+                );
+
+                let res: GetMultipleAccountsResponse = self
+                    .make_request(JsonRpcMethod::GetMultipleAccounts, Some(params))
+                    .await
+                    .and_then(|res| deserialize(&res))
+                    .map_err(|e| e.with_method(JsonRpcMethod::GetMultipleAccounts))?;
+
+                chunk
+                    .iter()
+                    .zip(res.result.value)
+                    .map(|(pubkey, account)| {
+                        account
+                            .map(|account| {
+                                decode_account(*pubkey, account, JsonRpcMethod::GetMultipleAccounts)
+                            })
+                            .transpose()
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            });
+
+        let chunks = try_join_all(chunk_futures).await?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+
+    pub async fn get_epoch_info(
+        &self,
+        config: Option<GetEpochInfoConfig>,
+    ) -> Result<EpochInfo, Error> {
+        let params: GetEpochInfoParams = (config,);
+
+        let res: GetEpochInfoResponse = self
+            .make_request(JsonRpcMethod::GetEpochInfo, Some(params))
+            .await
+            .and_then(|res| deserialize(&res))
+            .map_err(|e| e.with_method(JsonRpcMethod::GetEpochInfo))?;
+
+        Ok(res.result)
+    }
+
     pub async fn get_decimals(
         &self,
         mint: &Pubkey,
@@ -341,10 +573,8 @@ impl SolanaMirrorClient {
     ) -> Result<GetDecimalsResponse, Error> {
         let params: GetDecimalsParams = (mint.to_string(), config);
 
-        let res = self
-            .make_request(JsonRpcMethod::GetDecimals, Some(params))
-            .await?;
-        deserialize::<GetDecimalsResponse>(&res)
+        self.call(JsonRpcMethod::GetDecimals, &mint.to_string(), params)
+            .await
     }
 
     pub async fn get_signatures_for_address(
@@ -354,10 +584,12 @@ impl SolanaMirrorClient {
     ) -> Result<GetSignaturesForAddressResponse, Error> {
         let params: GetSignaturesForAddressParams = (address.to_string(), config);
 
-        let res = self
-            .make_request(JsonRpcMethod::GetSignaturesForAddress, Some(params))
-            .await?;
-        deserialize::<GetSignaturesForAddressResponse>(&res)
+        self.call(
+            JsonRpcMethod::GetSignaturesForAddress,
+            &address.to_string(),
+            params,
+        )
+        .await
     }
 
     pub async fn get_transactions(
@@ -378,7 +610,38 @@ impl SolanaMirrorClient {
             })
             .collect();
 
-        let res = self.make_batch_request(&body).await?;
-        deserialize::<Vec<GetTransactionResponse>>(&res)
+        let res = self
+            .make_batch_request(&body)
+            .await
+            .map_err(|e| e.with_method(JsonRpcMethod::GetTransaction))?;
+
+        // Deserialize one by one so a failure points to the signature that caused it
+        let responses = match res {
+            Value::Array(responses) => responses,
+            // The node rejected the batch as a whole
+            res => {
+                return deserialize::<Vec<GetTransactionResponse>>(&res)
+                    .map_err(|e| e.with_method(JsonRpcMethod::GetTransaction))
+            }
+        };
+
+        responses
+            .iter()
+            .map(|res| {
+                deserialize::<GetTransactionResponse>(res).map_err(|e| {
+                    let e = e.with_method(JsonRpcMethod::GetTransaction);
+                    let signature = body
+                        .iter()
+                        .find(|req| res["id"].as_str() == Some(req.id.as_str()))
+                        .and_then(|req| req.params.as_ref())
+                        .map(|(signature, _)| signature);
+
+                    match signature {
+                        Some(signature) => e.with_address(signature),
+                        None => e,
+                    }
+                })
+            })
+            .collect()
     }
 }
@@ -0,0 +1,86 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+use rocket::tokio::time::sleep;
+
+use crate::Error;
+
+/// Controls how requests that failed with a transient error are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every following attempt
+    pub base_delay: Duration,
+    /// Upper bound for a single wait, also applied to `Retry-After`
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends every request exactly once
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Exponential backoff with full jitter: a random wait between 0 and `base_delay * 2^attempt`
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+        exp.mul_f64(jitter)
+    }
+
+    fn delay_for(&self, err: &Error, attempt: u32) -> Duration {
+        match err {
+            Error::TooManyRequests {
+                retry_after: Some(retry_after),
+                ..
+            } => (*retry_after).min(self.max_delay),
+            _ => self.backoff(attempt),
+        }
+    }
+}
+
+/// Only network failures and rate limits are worth retrying, a response that
+/// doesn't parse will fail the same way on the next attempt
+pub(crate) fn is_retryable(err: &Error) -> bool {
+    matches!(err, Error::FetchError(_) | Error::TooManyRequests { .. })
+}
+
+pub(crate) async fn retry<T, F, Fut>(policy: &RetryPolicy, mut callback: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        match callback().await {
+            Ok(res) => return Ok(res),
+            Err(e) => {
+                attempt += 1;
+                if attempt >= policy.max_attempts || !is_retryable(&e) {
+                    return Err(e);
+                }
+
+                sleep(policy.delay_for(&e, attempt - 1)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::{header::RETRY_AFTER, Client, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::Error;
+
+use super::JsonRpcMethod;
+
+/// Sends a JSON-RPC payload (a single request or a batch) to a node and returns the raw response
+#[rocket::async_trait]
+pub trait RpcTransport: Send + Sync {
+    async fn send(&self, body: &Value) -> Result<Value, Error>;
+
+    /// Url of the node requests go to, `None` for transports that don't talk to one
+    fn rpc_url(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Posts requests to an RPC node over HTTP
+pub struct HttpTransport {
+    inner_client: Client,
+    pub rpc_url: String,
+}
+
+impl HttpTransport {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            inner_client: Client::new(),
+            rpc_url,
+        }
+    }
+
+    pub fn from_client(inner_client: &Client, rpc_url: String) -> Self {
+        Self {
+            inner_client: inner_client.clone(),
+            rpc_url,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl RpcTransport for HttpTransport {
+    async fn send(&self, body: &Value) -> Result<Value, Error> {
+        let serialized = match serde_json::to_string(body) {
+            Ok(serialized) => serialized,
+            Err(e) => return Err(Error::parse_error().with_source(e)),
+        };
+
+        let req = self
+            .inner_client
+            .post(&self.rpc_url)
+            .header("Content-Type", "application/json")
+            .header("solana-client", "js/0.0.0-development")
+            .body(serialized);
+
+        match req.send().await {
+            Ok(response) => {
+                let status = response.status();
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    return Err(Error::too_many_requests(retry_after(&response)));
+                }
+                if status.is_server_error() {
+                    return Err(Error::fetch_error().with_source(format!("HTTP {}", status)));
+                }
+
+                let res = response
+                    .json::<Value>()
+                    .await
+                    .map_err(|e| Error::parse_error().with_source(e))?;
+                Ok(res)
+            }
+            Err(e) => Err(Error::fetch_error().with_source(e)),
+        }
+    }
+
+    fn rpc_url(&self) -> Option<&str> {
+        Some(&self.rpc_url)
+    }
+}
+
+/// Reads the `Retry-After` header, only the delay-seconds form is supported
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Answers JSON-RPC requests from canned responses, so the client can run without a node.
+/// Responses registered for params containing some text come first, then the ones for
+/// a specific first param (eg. an address or a signature), then the ones for the whole method
+#[derive(Default)]
+pub struct FixtureTransport {
+    results: HashMap<String, Value>,
+    keyed_results: HashMap<(String, String), Value>,
+    matching_results: Vec<(String, String, Value)>,
+    errors: HashMap<String, (i32, String)>,
+}
+
+impl FixtureTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers every call to `method` with `result`
+    pub fn with_result(mut self, method: JsonRpcMethod, result: Value) -> Self {
+        self.results.insert(method.to_string(), result);
+        self
+    }
+
+    /// Answers calls to `method` whose first param is `param` with `result`
+    pub fn with_result_for(mut self, method: JsonRpcMethod, param: &str, result: Value) -> Self {
+        self.keyed_results
+            .insert((method.to_string(), param.to_string()), result);
+        self
+    }
+
+    /// Answers calls to `method` whose serialized params contain `needle` with `result`,
+    /// for calls told apart by something other than their first param (eg. a program filter)
+    pub fn with_result_containing(
+        mut self,
+        method: JsonRpcMethod,
+        needle: &str,
+        result: Value,
+    ) -> Self {
+        self.matching_results
+            .push((method.to_string(), needle.to_string(), result));
+        self
+    }
+
+    /// Answers every call to `method` with a JSON-RPC error
+    pub fn with_error(mut self, method: JsonRpcMethod, code: i32, message: &str) -> Self {
+        self.errors
+            .insert(method.to_string(), (code, message.to_string()));
+        self
+    }
+
+    fn answer(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let first_param = request["params"][0]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        if let Some((code, message)) = self.errors.get(&method) {
+            return json!({
+                "jsonrpc": "2.0",
+                "error": { "code": code, "message": message },
+                "id": id,
+            });
+        }
+
+        let params = request["params"].to_string();
+        let result = self
+            .matching_results
+            .iter()
+            .find(|(m, needle, _)| *m == method && params.contains(needle.as_str()))
+            .map(|(_, _, result)| result)
+            .or_else(|| self.keyed_results.get(&(method.clone(), first_param)))
+            .or_else(|| self.results.get(&method));
+
+        match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                "id": id,
+            }),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl RpcTransport for FixtureTransport {
+    async fn send(&self, body: &Value) -> Result<Value, Error> {
+        match body {
+            Value::Array(requests) => Ok(Value::Array(
+                requests
+                    .iter()
+                    .map(|request| self.answer(request))
+                    .collect(),
+            )),
+            request => Ok(self.answer(request)),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
 
 // get_token_accounts_by_owner
 #[derive(Serialize, Deserialize, Debug)]
@@ -55,6 +56,116 @@ pub struct AccountInfo {
     pub state: String,
     #[serde(rename = "tokenAmount")]
     pub token_amount: TokenAmount,
+    /// Only present on Token-2022 accounts
+    #[serde(default)]
+    pub extensions: Vec<TokenExtension>,
+}
+
+/// Token-2022 extensions as returned by the jsonParsed encoding, for both accounts and mints
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "extension", content = "state", try_from = "RawTokenExtension")]
+pub enum TokenExtension {
+    #[serde(rename = "transferFeeConfig")]
+    TransferFeeConfig(TransferFeeConfig),
+    #[serde(rename = "transferFeeAmount")]
+    TransferFeeAmount(TransferFeeAmount),
+    #[serde(rename = "interestBearingConfig")]
+    InterestBearingConfig(InterestBearingConfig),
+    #[serde(rename = "metadataPointer")]
+    MetadataPointer(MetadataPointer),
+    #[serde(rename = "tokenMetadata")]
+    TokenMetadata(TokenMetadata),
+    /// Extensions we don't read, their state is dropped
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+#[derive(Deserialize)]
+struct RawTokenExtension {
+    extension: String,
+    #[serde(default)]
+    state: Value,
+}
+
+impl TryFrom<RawTokenExtension> for TokenExtension {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawTokenExtension) -> Result<Self, Self::Error> {
+        let extension = match raw.extension.as_str() {
+            "transferFeeConfig" => Self::TransferFeeConfig(serde_json::from_value(raw.state)?),
+            "transferFeeAmount" => Self::TransferFeeAmount(serde_json::from_value(raw.state)?),
+            "interestBearingConfig" => {
+                Self::InterestBearingConfig(serde_json::from_value(raw.state)?)
+            }
+            "metadataPointer" => Self::MetadataPointer(serde_json::from_value(raw.state)?),
+            "tokenMetadata" => Self::TokenMetadata(serde_json::from_value(raw.state)?),
+            _ => Self::Unknown,
+        };
+
+        Ok(extension)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferFeeConfig {
+    #[serde(rename = "transferFeeConfigAuthority")]
+    pub transfer_fee_config_authority: Option<String>,
+    #[serde(rename = "withdrawWithheldAuthority")]
+    pub withdraw_withheld_authority: Option<String>,
+    #[serde(rename = "withheldAmount")]
+    pub withheld_amount: u64,
+    #[serde(rename = "olderTransferFee")]
+    pub older_transfer_fee: TransferFee,
+    #[serde(rename = "newerTransferFee")]
+    pub newer_transfer_fee: TransferFee,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferFee {
+    pub epoch: u64,
+    #[serde(rename = "maximumFee")]
+    pub maximum_fee: u64,
+    #[serde(rename = "transferFeeBasisPoints")]
+    pub transfer_fee_basis_points: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransferFeeAmount {
+    #[serde(rename = "withheldAmount")]
+    pub withheld_amount: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterestBearingConfig {
+    #[serde(rename = "rateAuthority")]
+    pub rate_authority: Option<String>,
+    #[serde(rename = "initializationTimestamp")]
+    pub initialization_timestamp: i64,
+    #[serde(rename = "preUpdateAverageRate")]
+    pub pre_update_average_rate: i16,
+    #[serde(rename = "lastUpdateTimestamp")]
+    pub last_update_timestamp: i64,
+    #[serde(rename = "currentRate")]
+    pub current_rate: i16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MetadataPointer {
+    pub authority: Option<String>,
+    #[serde(rename = "metadataAddress")]
+    pub metadata_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TokenMetadata {
+    #[serde(rename = "updateAuthority")]
+    pub update_authority: Option<String>,
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    #[serde(rename = "additionalMetadata", default)]
+    pub additional_metadata: Vec<(String, String)>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -93,6 +204,91 @@ pub struct AccountMetadataInfo {
     pub space: u64,
 }
 
+// get_multiple_accounts
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MultipleAccountsResultData {
+    pub context: Context,
+    pub value: Vec<Option<AccountMetadataInfo>>,
+}
+
+// get_program_accounts
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProgramAccount {
+    pub pubkey: String,
+    pub account: AccountMetadataInfo,
+}
+
+/// An account owned by a program, with its data already decoded
+#[derive(Debug, Clone)]
+pub struct KeyedAccount {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: String,
+    pub data: Vec<u8>,
+}
+
+// get_epoch_info
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EpochInfo {
+    #[serde(rename = "absoluteSlot")]
+    pub absolute_slot: u64,
+    #[serde(rename = "blockHeight")]
+    pub block_height: u64,
+    pub epoch: u64,
+    #[serde(rename = "slotIndex")]
+    pub slot_index: u64,
+    #[serde(rename = "slotsInEpoch")]
+    pub slots_in_epoch: u64,
+    #[serde(rename = "transactionCount")]
+    pub transaction_count: Option<u64>,
+}
+
+// get_mint
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintResultData {
+    pub context: Context,
+    pub value: Option<MintAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintAccount {
+    pub data: MintData,
+    pub lamports: u64,
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MintData {
+    pub parsed: ParsedMint,
+    pub program: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ParsedMint {
+    pub info: MintInfo,
+    #[serde(rename = "type")]
+    pub account_type_str: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MintInfo {
+    pub decimals: u8,
+    #[serde(rename = "freezeAuthority")]
+    pub freeze_authority: Option<String>,
+    #[serde(rename = "mintAuthority")]
+    pub mint_authority: Option<String>,
+    #[serde(rename = "isInitialized")]
+    pub is_initialized: bool,
+    pub supply: String,
+    /// Only present on Token-2022 mints
+    #[serde(default)]
+    pub extensions: Vec<TokenExtension>,
+}
+
 // get_decimals
 
 #[derive(Serialize, Deserialize, Debug)]
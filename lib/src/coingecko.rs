@@ -1,10 +1,15 @@
 use std::{collections::HashMap, env, fs::File, io::BufReader};
 
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
 use serde_json::{from_reader, Value};
 
-use crate::{chart::types::GetCoinMarketChartParams, Error};
+use crate::{
+    chart::types::GetCoinMarketChartParams,
+    client::limiter::{RateLimit, RateLimiter},
+    utils::get_rate_limit,
+    Error,
+};
 
 const BASE_URL: &str = "https://api.coingecko.com/api/v3";
 
@@ -23,7 +28,7 @@ pub async fn get_coingecko_data() -> Result<CoingeckoData, Error> {
         Ok(file) => file,
         Err(e) => {
             eprintln!("Failed to open file: {}", e);
-            return Err(Error::ParseError);
+            return Err(Error::parse_error().with_source(e));
         }
     };
 
@@ -33,7 +38,7 @@ pub async fn get_coingecko_data() -> Result<CoingeckoData, Error> {
         Ok(data) => Ok(data),
         Err(e) => {
             eprintln!("Failed to parse file: {}", e);
-            Err(Error::ParseError)
+            Err(Error::parse_error().with_source(e))
         }
     }
 }
@@ -41,10 +46,7 @@ pub async fn get_coingecko_data() -> Result<CoingeckoData, Error> {
 /// Returns the coingecko ID from a mint from the coingecko.json file
 pub async fn get_coingecko_id(mint: &str) -> Option<String> {
     match get_coingecko_data().await {
-        Ok(data) => match data.get(mint) {
-            Some(token) => Some(token.id.clone()),
-            None => None,
-        },
+        Ok(data) => data.get(mint).map(|token| token.id.clone()),
         Err(_) => None,
     }
 }
@@ -52,46 +54,69 @@ pub async fn get_coingecko_id(mint: &str) -> Option<String> {
 pub struct CoingeckoClient {
     pub inner_client: Client,
     pub api_key: Option<String>,
+    limiter: RateLimiter,
+}
+
+/// The demo plan allows 30 calls per minute
+fn default_rate_limit() -> RateLimit {
+    RateLimit {
+        requests_per_second: 0.5,
+        burst: 5,
+        max_in_flight: 4,
+    }
+}
+
+impl Default for CoingeckoClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CoingeckoClient {
     pub fn new() -> Self {
-        let api_key = match env::var("COINGECKO_API_KEY") {
-            Ok(key) => Some(key),
-            _ => None,
-        };
+        let api_key = env::var("COINGECKO_API_KEY").ok();
 
         Self {
             inner_client: Client::new(),
             api_key,
+            limiter: RateLimiter::new(get_rate_limit("COINGECKO", default_rate_limit())),
         }
     }
 
     pub fn from_client(inner_client: &Client) -> Self {
-        let api_key = match env::var("COINGECKO_API_KEY") {
-            Ok(key) => Some(key),
-            _ => None,
-        };
+        let api_key = env::var("COINGECKO_API_KEY").ok();
 
         Self {
             inner_client: inner_client.clone(),
             api_key,
+            limiter: RateLimiter::new(get_rate_limit("COINGECKO", default_rate_limit())),
         }
     }
 
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.limiter = RateLimiter::new(rate_limit);
+        self
+    }
+
     async fn make_request(&self, endpoint: &str, query: &[(&str, String)]) -> Result<Value, Error> {
         let request = self.inner_client.get(endpoint).query(query);
+        let _permit = self.limiter.acquire().await;
 
-        match request.send().await {
+        let res = match request.send().await {
             Ok(response) => {
-                let res = response
-                    .json::<Value>()
-                    .await
-                    .map_err(|_| Error::ParseError)?;
-                Ok(res)
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    Err(Error::too_many_requests(None))
+                } else {
+                    response
+                        .json::<Value>()
+                        .await
+                        .map_err(|e| Error::parse_error().with_source(e))
+                }
             }
-            Err(_) => Err(Error::FetchError),
-        }
+            Err(e) => Err(Error::fetch_error().with_source(e)),
+        };
+
+        res.map_err(|e| e.with_method(endpoint))
     }
 
     pub async fn get_coin_market_chart(
@@ -127,4 +152,23 @@ impl CoingeckoClient {
 
         Ok(prices)
     }
+
+    /// Gets the current USD price of several coins at once, leaving out the ones CoinGecko doesn't price
+    pub async fn get_simple_prices(&self, ids: &[String]) -> Result<HashMap<String, f64>, Error> {
+        let endpoint = format!("{}/simple/price", BASE_URL);
+        let mut query = vec![("ids", ids.join(",")), ("vs_currencies", "usd".to_string())];
+
+        if let Some(key) = &self.api_key {
+            query.push(("x_cg_demo_api_key", key.clone()));
+        };
+
+        let res = self.make_request(&endpoint, &query).await?;
+
+        let prices = ids
+            .iter()
+            .filter_map(|id| Some((id.clone(), res[id]["usd"].as_f64()?)))
+            .collect();
+
+        Ok(prices)
+    }
 }
@@ -0,0 +1,161 @@
+use std::{error::Error as StdError, fmt, time::Duration};
+
+type Source = Box<dyn StdError + Send + Sync>;
+
+/// Where an error happened, filled in as it bubbles up through the client
+#[derive(Debug, Default)]
+pub struct ErrorContext {
+    /// The JSON-RPC method (or upstream endpoint) that failed
+    pub method: Option<String>,
+    /// The address (account, mint or signature) the request was about
+    pub address: Option<String>,
+    /// The underlying reqwest, serde or decoding error
+    pub source: Option<Source>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidAddress,
+    InvalidIndex,
+    InvalidTimeframe,
+    InvalidValuation,
+    /// More addresses than a single request can span
+    TooManyAddresses,
+    /// The request never got a usable response (DNS, connection, timeout, 5xx)
+    FetchError(ErrorContext),
+    /// The response didn't have the expected shape
+    ParseError(ErrorContext),
+    /// The node answered with a JSON-RPC error
+    RpcError {
+        code: i64,
+        message: String,
+        context: ErrorContext,
+    },
+    TooManyRequests {
+        /// The wait requested by the upstream through `Retry-After`, if any
+        retry_after: Option<Duration>,
+        context: ErrorContext,
+    },
+}
+
+impl Error {
+    pub fn fetch_error() -> Self {
+        Error::FetchError(ErrorContext::default())
+    }
+
+    pub fn parse_error() -> Self {
+        Error::ParseError(ErrorContext::default())
+    }
+
+    pub fn rpc_error(code: i64, message: String) -> Self {
+        Error::RpcError {
+            code,
+            message,
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn too_many_requests(retry_after: Option<Duration>) -> Self {
+        Error::TooManyRequests {
+            retry_after,
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::FetchError(context)
+            | Error::ParseError(context)
+            | Error::RpcError { context, .. }
+            | Error::TooManyRequests { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    fn context_mut(&mut self) -> Option<&mut ErrorContext> {
+        match self {
+            Error::FetchError(context)
+            | Error::ParseError(context)
+            | Error::RpcError { context, .. }
+            | Error::TooManyRequests { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::TooManyRequests { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<Source>) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.source = Some(source.into());
+        }
+        self
+    }
+
+    /// Sets the method, keeping the innermost one if it was already set
+    pub fn with_method(mut self, method: impl ToString) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.method.get_or_insert_with(|| method.to_string());
+        }
+        self
+    }
+
+    /// Sets the address, keeping the innermost one if it was already set
+    pub fn with_address(mut self, address: impl ToString) -> Self {
+        if let Some(context) = self.context_mut() {
+            context.address.get_or_insert_with(|| address.to_string());
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAddress => write!(f, "invalid address")?,
+            Error::InvalidIndex => write!(f, "invalid index")?,
+            Error::InvalidTimeframe => write!(f, "invalid timeframe")?,
+            Error::InvalidValuation => write!(f, "invalid valuation")?,
+            Error::TooManyAddresses => write!(f, "too many addresses")?,
+            Error::FetchError(_) => write!(f, "upstream request failed")?,
+            Error::ParseError(_) => write!(f, "failed to parse upstream response")?,
+            Error::RpcError { code, message, .. } => write!(f, "RPC error {}: {}", code, message)?,
+            Error::TooManyRequests { retry_after, .. } => {
+                write!(f, "rate limited by upstream")?;
+                if let Some(retry_after) = retry_after {
+                    write!(f, ", retry after {}s", retry_after.as_secs())?;
+                }
+            }
+        }
+
+        if let Some(context) = self.context() {
+            if let Some(method) = &context.method {
+                write!(f, " (method: {}", method)?;
+                if let Some(address) = &context.address {
+                    write!(f, ", address: {}", address)?;
+                }
+                write!(f, ")")?;
+            } else if let Some(address) = &context.address {
+                write!(f, " (address: {})", address)?;
+            }
+
+            if let Some(source) = &context.source {
+                write!(f, ": {}", source)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.context()
+            .and_then(|context| context.source.as_deref())
+            .map(|source| source as &(dyn StdError + 'static))
+    }
+}
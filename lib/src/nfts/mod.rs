@@ -0,0 +1,183 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use mpl_token_metadata::{
+    accounts::Metadata, programs::MPL_TOKEN_METADATA_ID, types::TokenStandard,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::accounts::{get_accounts, types::ImageResponse},
+    client::{types::AccountData, SolanaMirrorClient},
+    price::HTTP_CLIENT,
+    utils::{clean_string, fetch_metadata},
+    Error,
+};
+
+use types::{NftCollection, NftCreator, NftsResponse, ParsedNft};
+
+pub mod types;
+
+/// NFTs whose off-chain metadata is fetched at once, wallets can hold thousands
+const MAX_CONCURRENT_NFTS: usize = 16;
+
+/// Metadata hosts are arbitrary, a slow one shouldn't hold the whole response
+const NFT_METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fetches the NFTs held by the given address, grouped by their verified collection.
+/// Compressed NFTs don't live in token accounts, so they aren't listed
+pub async fn get_nfts(client: &SolanaMirrorClient, owner: &Pubkey) -> Result<NftsResponse, Error> {
+    let accounts = get_accounts(client, owner).await?;
+    let nft_accounts: Vec<&AccountData> = accounts.iter().filter(|a| is_nft(a)).collect();
+
+    let mpl_program_id = Pubkey::from_str(&MPL_TOKEN_METADATA_ID.to_string()).unwrap();
+    let metadata_addresses: Vec<Pubkey> = nft_accounts
+        .iter()
+        .map(|account| {
+            let mint = &account.account.data.parsed.info.mint;
+            let mint = Pubkey::from_str(mint)
+                .map_err(|e| Error::parse_error().with_source(e).with_address(mint))?;
+            let seeds = &[Metadata::PREFIX, mpl_program_id.as_ref(), mint.as_ref()];
+            Ok(Pubkey::find_program_address(seeds, &mpl_program_id).0)
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let metadata_accounts = client.get_multiple_accounts(&metadata_addresses).await?;
+
+    // Tokens without Metaplex metadata aren't NFTs, even with a supply of one
+    let parse_futures = nft_accounts
+        .into_iter()
+        .zip(metadata_accounts)
+        .filter_map(|(account, metadata)| {
+            let metadata = Metadata::safe_deserialize(&metadata?.data).ok()?;
+            Some(parse_nft(account, metadata))
+        })
+        .collect::<Vec<_>>();
+    let nfts: Vec<ParsedNft> = stream::iter(parse_futures)
+        .buffered(MAX_CONCURRENT_NFTS)
+        .collect()
+        .await;
+
+    let count = nfts.len();
+    let mut collections: Vec<NftCollection> = Vec::new();
+    let mut uncategorized = Vec::new();
+
+    for nft in nfts {
+        let Some(collection_address) = nft.collection.clone() else {
+            uncategorized.push(nft);
+            continue;
+        };
+
+        match collections
+            .iter_mut()
+            .find(|collection| collection.address == collection_address)
+        {
+            Some(collection) => collection.nfts.push(nft),
+            None => collections.push(NftCollection {
+                address: collection_address,
+                nfts: vec![nft],
+                ..Default::default()
+            }),
+        }
+    }
+
+    // The collection NFT carries the name and image of the whole collection
+    let collection_futures: Vec<_> = collections
+        .iter_mut()
+        .map(|collection| async move {
+            let metadata = fetch_metadata(client, &collection.address, None).await;
+            collection.image = fetch_nft_image(&metadata.uri).await;
+            collection.name = metadata.name;
+            collection.symbol = metadata.symbol;
+        })
+        .collect();
+    stream::iter(collection_futures)
+        .buffer_unordered(MAX_CONCURRENT_NFTS)
+        .collect::<Vec<()>>()
+        .await;
+
+    Ok(NftsResponse {
+        collections,
+        uncategorized,
+        count,
+    })
+}
+
+fn is_nft(account: &AccountData) -> bool {
+    let token_amount = &account.account.data.parsed.info.token_amount;
+    token_amount.decimals == 0 && token_amount.amount == "1"
+}
+
+async fn parse_nft(account: &AccountData, metadata: Metadata) -> ParsedNft {
+    let uri = clean_string(metadata.uri);
+    let image = fetch_nft_image(&uri).await;
+
+    let collection = metadata
+        .collection
+        .filter(|collection| collection.verified)
+        .map(|collection| collection.key.to_string());
+
+    let creators = metadata
+        .creators
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|creator| creator.verified)
+        .map(|creator| NftCreator {
+            address: creator.address.to_string(),
+            share: creator.share,
+        })
+        .collect();
+
+    ParsedNft {
+        mint: account.account.data.parsed.info.mint.clone(),
+        ata: account.pubkey.clone(),
+        name: clean_string(metadata.name),
+        symbol: clean_string(metadata.symbol),
+        uri,
+        image,
+        collection,
+        creators,
+        royalties_bps: metadata.seller_fee_basis_points,
+        token_standard: metadata.token_standard.map(format_token_standard),
+        program: account.account.data.program.clone(),
+    }
+}
+
+/// Every NFT of a collection shares the symbol, and collections often share or leave out
+/// theirs, so images can't go through the symbol keyed cache of `fetch_image`.
+/// The uri is set by the creator, only web urls are followed
+async fn fetch_nft_image(uri: &str) -> String {
+    let Ok(url) = reqwest::Url::parse(uri) else {
+        return String::default();
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return String::default();
+    }
+
+    match HTTP_CLIENT
+        .get(url)
+        .timeout(NFT_METADATA_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response
+            .json::<ImageResponse>()
+            .await
+            .map(|image_response| image_response.image)
+            .unwrap_or_default(),
+        Err(_) => String::default(),
+    }
+}
+
+fn format_token_standard(token_standard: TokenStandard) -> String {
+    let token_standard = match token_standard {
+        TokenStandard::NonFungible => "nonFungible",
+        TokenStandard::FungibleAsset => "fungibleAsset",
+        TokenStandard::Fungible => "fungible",
+        TokenStandard::NonFungibleEdition => "nonFungibleEdition",
+        TokenStandard::ProgrammableNonFungible => "programmableNonFungible",
+        TokenStandard::ProgrammableNonFungibleEdition => "programmableNonFungibleEdition",
+    };
+    token_standard.to_string()
+}
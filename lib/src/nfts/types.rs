@@ -0,0 +1,50 @@
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize)]
+pub struct NftsResponse {
+    /// NFTs that belong to a verified collection, grouped by it
+    pub collections: Vec<NftCollection>,
+    /// NFTs without a collection, or whose collection isn't verified
+    pub uncategorized: Vec<ParsedNft>,
+    pub count: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct NftCollection {
+    /// Mint of the collection NFT
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+    pub image: String,
+    pub nfts: Vec<ParsedNft>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ParsedNft {
+    pub mint: String,
+    pub ata: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub image: String,
+    /// Mint of the collection, only set once the collection verified it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+    /// Creators that signed the metadata, unverified ones can be set by anyone
+    pub creators: Vec<NftCreator>,
+    /// Royalties paid to the creators on secondary sales, in basis points
+    #[serde(rename = "royaltiesBps")]
+    pub royalties_bps: u16,
+    /// Metaplex token standard, eg. `nonFungible` or `programmableNonFungible`
+    #[serde(rename = "tokenStandard", skip_serializing_if = "Option::is_none")]
+    pub token_standard: Option<String>,
+    /// Owner program of the token, either `spl-token` or `spl-token-2022`
+    pub program: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct NftCreator {
+    pub address: String,
+    /// Percentage of the royalties that goes to the creator
+    pub share: u8,
+}
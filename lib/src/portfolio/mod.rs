@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use futures::future::try_join_all;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::{
+        accounts::types::ParsedAta, by_value_desc, dapps::registry::DappRegistry, get_totals,
+        get_wallet_balances, get_weight, is_dust, types::BalancesTotals,
+    },
+    client::SolanaMirrorClient,
+    types::FormattedAmount,
+    Error,
+};
+
+use types::{PortfolioAsset, PortfolioResponse, WalletHolding, WalletTotals};
+
+pub mod types;
+
+/// Most wallets a single portfolio request can span
+pub const MAX_PORTFOLIO_WALLETS: usize = 10;
+
+/// Fetches the balances of every wallet and merges them, summing the tokens that share a mint
+pub async fn get_portfolio(
+    client: &SolanaMirrorClient,
+    registry: &DappRegistry,
+    pubkeys: &[Pubkey],
+    show_apps: bool,
+    hide_spam: bool,
+    min_usd: Option<f64>,
+) -> Result<PortfolioResponse, Error> {
+    let wallets = try_join_all(
+        pubkeys
+            .iter()
+            .map(|pubkey| get_wallet_balances(client, registry, pubkey, show_apps, hide_spam)),
+    )
+    .await?;
+
+    let mut totals = BalancesTotals::default();
+    let mut wallet_totals = Vec::with_capacity(wallets.len());
+    let mut assets: Vec<PortfolioAsset> = Vec::new();
+    let mut stake = Vec::new();
+    let mut positions = Vec::new();
+    let mut lending = Vec::new();
+
+    for (pubkey, wallet) in pubkeys.iter().zip(wallets) {
+        let wallet_total = get_totals(&wallet);
+        totals.tokens += wallet_total.tokens;
+        totals.positions += wallet_total.positions;
+        totals.staked += wallet_total.staked;
+        totals.lending += wallet_total.lending;
+        totals.total += wallet_total.total;
+        totals.gross += wallet_total.gross;
+        wallet_totals.push(WalletTotals {
+            address: pubkey.to_string(),
+            totals: wallet_total,
+        });
+
+        for account in wallet.accounts {
+            match assets.iter_mut().find(|asset| asset.mint == account.mint) {
+                Some(asset) => add_holding(asset, pubkey, account),
+                None => assets.push(new_asset(pubkey, account)),
+            }
+        }
+
+        stake.extend(wallet.stake);
+        positions.extend(wallet.positions);
+        lending.extend(wallet.lending);
+    }
+
+    assets
+        .iter_mut()
+        .for_each(|a| a.weight = get_weight(a.value_usd, &totals));
+    stake
+        .iter_mut()
+        .for_each(|s| s.weight = get_weight(s.value_usd, &totals));
+    positions
+        .iter_mut()
+        .for_each(|p| p.weight = get_weight(p.total_value_usd, &totals));
+
+    if let Some(min_usd) = min_usd {
+        assets.retain(|a| !is_dust(a.value_usd, min_usd));
+        stake.retain(|s| !is_dust(s.value_usd, min_usd));
+        positions.retain(|p| !is_dust(p.total_value_usd, min_usd));
+    }
+
+    assets.sort_by(|a, b| by_value_desc(a.value_usd, b.value_usd));
+    stake.sort_by(|a, b| by_value_desc(a.value_usd, b.value_usd));
+    positions.sort_by(|a, b| by_value_desc(a.total_value_usd, b.total_value_usd));
+
+    Ok(PortfolioResponse {
+        addresses: pubkeys.iter().map(Pubkey::to_string).collect(),
+        totals,
+        assets,
+        stake,
+        positions,
+        lending,
+        wallets: wallet_totals,
+    })
+}
+
+fn new_asset(pubkey: &Pubkey, account: ParsedAta) -> PortfolioAsset {
+    let mut asset = PortfolioAsset {
+        mint: account.mint.clone(),
+        coingecko_id: account.coingecko_id.clone(),
+        decimals: account.decimals,
+        name: account.name.clone(),
+        symbol: account.symbol.clone(),
+        image: account.image.clone(),
+        price: account.price,
+        price_quote: account.price_quote.clone(),
+        spam: account.spam,
+        is_lst: account.is_lst,
+        ..Default::default()
+    };
+    add_holding(&mut asset, pubkey, account);
+    asset
+}
+
+fn add_holding(asset: &mut PortfolioAsset, pubkey: &Pubkey, account: ParsedAta) {
+    // Amounts are summed as integers, the formatted ones could lose precision
+    let amount = asset.balance.amount.parse::<u128>().unwrap_or_default()
+        + account.balance.amount.parse::<u128>().unwrap_or_default();
+    asset.balance = FormattedAmount {
+        amount: amount.to_string(),
+        formatted: asset.balance.formatted + account.balance.formatted,
+    };
+
+    asset.value_usd = match (asset.value_usd, account.value_usd) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    };
+    asset.underlying_sol = match (asset.underlying_sol, account.underlying_sol) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    };
+
+    asset.holdings.push(WalletHolding {
+        address: pubkey.to_string(),
+        ata: account.ata,
+        balance: account.balance,
+        value_usd: account.value_usd,
+    });
+}
+
+/// Parses a comma separated list of addresses, dropping the repeated ones
+pub fn parse_addresses(addresses: &str) -> Result<Vec<Pubkey>, Error> {
+    let mut pubkeys: Vec<Pubkey> = Vec::new();
+
+    for address in addresses.split(',').map(str::trim) {
+        let pubkey = Pubkey::from_str(address).map_err(|_| Error::InvalidAddress)?;
+        if !pubkeys.contains(&pubkey) {
+            pubkeys.push(pubkey);
+        }
+    }
+
+    if pubkeys.len() > MAX_PORTFOLIO_WALLETS {
+        return Err(Error::TooManyAddresses);
+    }
+
+    Ok(pubkeys)
+}
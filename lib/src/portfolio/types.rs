@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+use crate::{
+    balances::{
+        dapps::types::ParsedPosition, lending::types::LendingObligation,
+        stake::types::ParsedStakeAccount, types::BalancesTotals,
+    },
+    price::PriceQuote,
+    types::FormattedAmount,
+};
+
+#[derive(Serialize)]
+pub struct PortfolioResponse {
+    pub addresses: Vec<String>,
+    /// Totals over every wallet, dust included
+    pub totals: BalancesTotals,
+    /// Tokens summed over every wallet, one entry per mint
+    pub assets: Vec<PortfolioAsset>,
+    pub stake: Vec<ParsedStakeAccount>,
+    pub positions: Vec<ParsedPosition>,
+    /// Obligations have no weight, their deposits only count toward `totals.gross`
+    pub lending: Vec<LendingObligation>,
+    /// Totals of each wallet on its own
+    pub wallets: Vec<WalletTotals>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PortfolioAsset {
+    pub mint: String,
+    #[serde(rename = "coingeckoId")]
+    pub coingecko_id: Option<String>,
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    pub image: String,
+    pub price: Option<f64>,
+    #[serde(rename = "priceQuote", skip_serializing_if = "Option::is_none")]
+    pub price_quote: Option<PriceQuote>,
+    pub balance: FormattedAmount,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+    /// Share of the portfolio gross value (`totals.gross`), between 0 and 1
+    pub weight: Option<f64>,
+    pub spam: bool,
+    #[serde(rename = "isLst")]
+    pub is_lst: bool,
+    #[serde(rename = "underlyingSol", skip_serializing_if = "Option::is_none")]
+    pub underlying_sol: Option<f64>,
+    /// How much of the asset each wallet holds
+    pub holdings: Vec<WalletHolding>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WalletHolding {
+    pub address: String,
+    pub ata: String,
+    pub balance: FormattedAmount,
+    #[serde(rename = "valueUsd")]
+    pub value_usd: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletTotals {
+    pub address: String,
+    pub totals: BalancesTotals,
+}
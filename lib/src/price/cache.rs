@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use rocket::tokio::sync::watch;
+use solana_sdk::pubkey::Pubkey;
+
+use super::{PriceQuote, Prices};
+
+/// How long a token no source knows is remembered, so spam tokens
+/// don't go through the whole chain on every request. Failed lookups aren't remembered
+pub const MISSING_PRICE_TTL: Duration = Duration::from_secs(5 * 60);
+
+struct CachedPrice {
+    quote: Option<PriceQuote>,
+    expires_at: Instant,
+}
+
+/// Outcome of the lookup of a mint
+#[derive(Clone)]
+pub enum Lookup {
+    Priced(PriceQuote),
+    Unknown,
+    Failed,
+}
+
+/// Result of a lookup in flight, shared with the callers that want the same mint meanwhile
+type InFlight = watch::Receiver<Option<Lookup>>;
+
+/// Process wide cache of prices, each entry lives as long as the TTL of the source that priced it.
+/// Concurrent misses on the same mint are coalesced into a single lookup
+#[derive(Default)]
+pub struct PriceCache {
+    entries: Mutex<HashMap<Pubkey, CachedPrice>>,
+    in_flight: Mutex<HashMap<Pubkey, InFlight>>,
+}
+
+/// The mints a caller has to look up itself, and how to publish their prices
+pub struct Claim {
+    pub mints: Vec<(Pubkey, u8)>,
+    senders: Vec<watch::Sender<Option<Lookup>>>,
+}
+
+impl PriceCache {
+    /// Splits the mints into the ones already cached, the ones someone else is looking up
+    /// and the ones the caller has to look up, which are then awaited by everyone else
+    pub fn claim(
+        &self,
+        mints: &[(Pubkey, u8)],
+    ) -> (HashMap<Pubkey, PriceQuote>, Vec<(Pubkey, InFlight)>, Claim) {
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        let mut cached = HashMap::new();
+        let mut pending = Vec::new();
+        let mut claim = Claim {
+            mints: Vec::new(),
+            senders: Vec::new(),
+        };
+
+        for (mint, decimals) in mints {
+            if let Some(entry) = entries.get(mint).filter(|entry| entry.expires_at > now) {
+                if let Some(quote) = &entry.quote {
+                    cached.insert(*mint, quote.clone());
+                }
+                continue;
+            }
+
+            // A lookup whose caller went away leaves a closed channel behind, it's claimed again
+            if let Some(receiver) = in_flight.get(mint).filter(|rx| rx.has_changed().is_ok()) {
+                pending.push((*mint, receiver.clone()));
+                continue;
+            }
+
+            let (sender, receiver) = watch::channel(None);
+            in_flight.insert(*mint, receiver);
+            claim.mints.push((*mint, *decimals));
+            claim.senders.push(sender);
+        }
+
+        (cached, pending, claim)
+    }
+
+    /// Stores the prices found for a claim and hands them to whoever is waiting on them.
+    /// Failed lookups are only handed over, the next caller looks them up again
+    pub fn complete(&self, claim: Claim, prices: &Prices, ttl: impl Fn(&PriceQuote) -> Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        for ((mint, _), sender) in claim.mints.into_iter().zip(claim.senders) {
+            let lookup = match prices.quotes.get(&mint) {
+                Some(quote) => Lookup::Priced(quote.clone()),
+                None if prices.failed.contains(&mint) => Lookup::Failed,
+                None => Lookup::Unknown,
+            };
+
+            let cached = match &lookup {
+                Lookup::Priced(quote) => Some((Some(quote.clone()), ttl(quote))),
+                Lookup::Unknown => Some((None, MISSING_PRICE_TTL)),
+                Lookup::Failed => None,
+            };
+            if let Some((quote, ttl)) = cached {
+                entries.insert(
+                    mint,
+                    CachedPrice {
+                        quote,
+                        expires_at: now + ttl,
+                    },
+                );
+            }
+
+            in_flight.remove(&mint);
+            sender.send_replace(Some(lookup));
+        }
+
+        // Drop the expired entries so tokens seen once don't stay forever
+        entries.retain(|_, entry| entry.expires_at > now);
+    }
+
+    /// Waits for the lookups other callers are running
+    pub async fn wait(pending: Vec<(Pubkey, InFlight)>) -> Prices {
+        let waits = pending.into_iter().map(|(mint, mut receiver)| async move {
+            // The channel closes without a value if the other caller was cancelled
+            let lookup = receiver
+                .wait_for(|lookup| lookup.is_some())
+                .await
+                .ok()
+                .and_then(|lookup| lookup.clone())
+                .unwrap_or(Lookup::Failed);
+            (mint, lookup)
+        });
+
+        let mut prices = Prices::default();
+        for (mint, lookup) in join_all(waits).await {
+            match lookup {
+                Lookup::Priced(quote) => {
+                    prices.quotes.insert(mint, quote);
+                }
+                Lookup::Unknown => {}
+                Lookup::Failed => {
+                    prices.failed.insert(mint);
+                }
+            }
+        }
+        prices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ttl(_: &PriceQuote) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    #[test]
+    fn remembers_unknown_tokens_but_not_failed_lookups() {
+        let cache = PriceCache::default();
+        let priced = Pubkey::new_unique();
+        let unknown = Pubkey::new_unique();
+        let failed = Pubkey::new_unique();
+        let mints = [(priced, 6), (unknown, 6), (failed, 6)];
+
+        let (cached, pending, claim) = cache.claim(&mints);
+        assert!(cached.is_empty() && pending.is_empty());
+        assert_eq!(claim.mints.len(), 3);
+
+        let prices = Prices {
+            quotes: HashMap::from([(priced, PriceQuote::new(2.0, "test"))]),
+            failed: [failed].into(),
+        };
+        cache.complete(claim, &prices, ttl);
+
+        let (cached, pending, claim) = cache.claim(&mints);
+        assert_eq!(cached.get(&priced).map(|quote| quote.price), Some(2.0));
+        assert!(!cached.contains_key(&unknown));
+        assert!(pending.is_empty());
+        assert_eq!(claim.mints, vec![(failed, 6)]);
+    }
+
+    #[rocket::async_test]
+    async fn hands_failed_lookups_to_the_waiting_callers() {
+        let cache = PriceCache::default();
+        let unknown = Pubkey::new_unique();
+        let failed = Pubkey::new_unique();
+        let mints = [(unknown, 6), (failed, 6)];
+
+        let (_, _, claim) = cache.claim(&mints);
+        let (_, pending, _) = cache.claim(&mints);
+        assert_eq!(pending.len(), 2);
+
+        let prices = Prices {
+            quotes: HashMap::new(),
+            failed: [failed].into(),
+        };
+        cache.complete(claim, &prices, ttl);
+
+        let waited = PriceCache::wait(pending).await;
+        assert!(waited.quotes.is_empty());
+        assert_eq!(waited.failed, [failed].into());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    client::SolanaMirrorClient,
+    coingecko::{get_coingecko_data, CoingeckoClient},
+    Error,
+};
+
+use super::{PriceSource, SourcePrices, HTTP_CLIENT};
+
+/// Reads the USD price from CoinGecko, only for the tokens listed in `coingecko.json`
+pub struct CoingeckoPriceSource {
+    coingecko: CoingeckoClient,
+}
+
+impl CoingeckoPriceSource {
+    pub fn new() -> Self {
+        Self {
+            coingecko: CoingeckoClient::from_client(&HTTP_CLIENT),
+        }
+    }
+}
+
+impl Default for CoingeckoPriceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[rocket::async_trait]
+impl PriceSource for CoingeckoPriceSource {
+    fn id(&self) -> &'static str {
+        "coingecko"
+    }
+
+    /// CoinGecko refreshes its prices about once a minute, and calls are rate limited
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(120)
+    }
+
+    async fn get_price(
+        &self,
+        client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<Option<f64>, Error> {
+        let prices = self.get_prices(client, &[(*mint, decimals)]).await;
+        if prices.failed.contains(mint) {
+            return Err(Error::fetch_error().with_address(mint));
+        }
+        Ok(prices.prices.get(mint).map(|price| price.price))
+    }
+
+    async fn get_prices(
+        &self,
+        _client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        // Without the listing no token is known, which a retry wouldn't change
+        let Ok(coingecko_data) = get_coingecko_data().await else {
+            return SourcePrices::default();
+        };
+
+        // Several mints can share a coin, eg. bridged versions of the same token
+        let mut ids: HashMap<String, Vec<Pubkey>> = HashMap::new();
+        for (mint, _) in mints {
+            if let Some(token) = coingecko_data.get(&mint.to_string()) {
+                ids.entry(token.id.clone()).or_default().push(*mint);
+            }
+        }
+
+        if ids.is_empty() {
+            return SourcePrices::default();
+        }
+
+        let id_list: Vec<String> = ids.keys().cloned().collect();
+        let Ok(prices) = self.coingecko.get_simple_prices(&id_list).await else {
+            return SourcePrices {
+                prices: HashMap::new(),
+                failed: ids.into_values().flatten().collect(),
+            };
+        };
+
+        let prices = prices
+            .into_iter()
+            .filter_map(|(id, price)| ids.get(&id).map(|mints| (mints, price)))
+            .flat_map(|(mints, price)| mints.iter().map(move |mint| (*mint, price.into())))
+            .collect();
+
+        SourcePrices {
+            prices,
+            failed: HashSet::new(),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use futures::{
+    future::join_all,
+    stream::{self, StreamExt},
+};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{client::SolanaMirrorClient, Error, USDC_ADDRESS};
+
+use super::{
+    PriceLiquidity, PriceSource, SourcePrice, SourcePrices, HTTP_CLIENT, MAX_CONCURRENT_LOOKUPS,
+};
+
+const QUOTE_API_URL: &str = "https://quote-api.jup.ag/v6";
+const PRICE_API_URL: &str = "https://api.jup.ag/price/v2";
+
+/// Quotes a swap of one whole token into USDC
+pub struct JupiterQuoteSource;
+
+#[derive(Deserialize)]
+struct QuoteResponse {
+    /// Base units of USDC, as a string
+    #[serde(rename = "outAmount")]
+    out_amount: String,
+    /// Fraction of the value lost to the depth of the pools, as a string
+    #[serde(rename = "priceImpactPct")]
+    price_impact_pct: String,
+    #[serde(rename = "routePlan")]
+    route_plan: Vec<serde_json::Value>,
+}
+
+/// A swap of some amount of a token into USDC
+pub struct SwapQuote {
+    /// USD received for the whole amount
+    pub value_usd: f64,
+    pub liquidity: PriceLiquidity,
+}
+
+/// Price impact at which a quote is considered worthless
+const MAX_PRICE_IMPACT: f64 = 0.1;
+
+/// Share of the confidence kept for every pool after the first one in the route
+const ROUTE_HOP_PENALTY: f64 = 0.95;
+
+impl JupiterQuoteSource {
+    /// Quotes a swap of `amount` base units of the mint into USDC,
+    /// `None` if Jupiter has no route for the token
+    pub async fn quote(&self, mint: &Pubkey, amount: u64) -> Result<Option<SwapQuote>, Error> {
+        let decimals_b = 6;
+        let endpoint = format!("{}/quote", QUOTE_API_URL);
+
+        let response = HTTP_CLIENT
+            .get(&endpoint)
+            .query(&[
+                ("inputMint", mint.to_string()),
+                ("outputMint", USDC_ADDRESS.to_string()),
+                ("amount", amount.to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| Error::fetch_error().with_source(e).with_method(&endpoint))?;
+
+        // Unroutable and untradable tokens are answered with a 400, any other
+        // error (eg. a rejected API key) says nothing about the token
+        let status = response.status();
+        if status == StatusCode::BAD_REQUEST {
+            return Ok(None);
+        } else if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::too_many_requests(None).with_method(&endpoint));
+        } else if !status.is_success() {
+            return Err(Error::fetch_error()
+                .with_source(format!("HTTP {}", status))
+                .with_method(&endpoint)
+                .with_address(mint));
+        }
+
+        let response = response
+            .json::<QuoteResponse>()
+            .await
+            .map_err(|e| Error::parse_error().with_source(e).with_method(&endpoint))?;
+
+        let (Ok(out_amount), Ok(price_impact_pct)) = (
+            response.out_amount.parse::<u64>(),
+            response.price_impact_pct.parse::<f64>(),
+        ) else {
+            return Err(Error::parse_error().with_method(&endpoint));
+        };
+        let price_impact_pct = price_impact_pct.abs();
+        let route_hops = response.route_plan.len();
+
+        Ok(Some(SwapQuote {
+            value_usd: out_amount as f64 / 10_f64.powi(decimals_b),
+            liquidity: PriceLiquidity {
+                price_impact_pct,
+                route_hops,
+                confidence: get_confidence(price_impact_pct, route_hops),
+            },
+        }))
+    }
+
+    async fn quote_whole_token(
+        &self,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<Option<SourcePrice>, Error> {
+        let Some(amount) = 10_u64.checked_pow(decimals as u32) else {
+            return Ok(None);
+        };
+        let quote = self.quote(mint, amount).await?;
+
+        Ok(quote.map(|quote| SourcePrice {
+            price: quote.value_usd,
+            liquidity: Some(quote.liquidity),
+        }))
+    }
+}
+
+/// Scores a quote between 0 and 1, decreasing linearly with the price impact
+/// until `MAX_PRICE_IMPACT` and a little more for every extra pool in the route
+pub fn get_confidence(price_impact_pct: f64, route_hops: usize) -> f64 {
+    let impact_score = (1.0 - price_impact_pct / MAX_PRICE_IMPACT).clamp(0.0, 1.0);
+    let extra_hops = route_hops.saturating_sub(1) as i32;
+    impact_score * ROUTE_HOP_PENALTY.powi(extra_hops)
+}
+
+#[rocket::async_trait]
+impl PriceSource for JupiterQuoteSource {
+    fn id(&self) -> &'static str {
+        "jupiter-quote"
+    }
+
+    /// Quotes follow the pools closely, so they go stale fast
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    async fn get_price(
+        &self,
+        _client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<Option<f64>, Error> {
+        let price = self.quote_whole_token(mint, decimals).await?;
+        Ok(price.map(|price| price.price))
+    }
+
+    async fn get_prices(
+        &self,
+        _client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        let quote_futures = mints.iter().copied().map(|(mint, decimals)| async move {
+            let price = self.quote_whole_token(&mint, decimals).await;
+            (mint, price)
+        });
+
+        stream::iter(quote_futures)
+            .buffer_unordered(MAX_CONCURRENT_LOOKUPS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The price API accepts up to 100 ids per request
+const MAX_PRICE_IDS: usize = 100;
+
+/// Reads the price Jupiter derives from its own routes, without quoting a swap
+pub struct JupiterPriceSource;
+
+#[derive(Deserialize)]
+struct PriceResponse {
+    data: HashMap<String, Option<PriceData>>,
+}
+
+#[derive(Deserialize)]
+struct PriceData {
+    /// The API returns the price as a string
+    price: String,
+}
+
+impl JupiterPriceSource {
+    /// Tokens Jupiter doesn't know are left out
+    async fn fetch_prices(&self, ids: &[String]) -> Result<HashMap<String, f64>, Error> {
+        let response = HTTP_CLIENT
+            .get(PRICE_API_URL)
+            .query(&[("ids", ids.join(","))])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                Error::fetch_error()
+                    .with_source(e)
+                    .with_method(PRICE_API_URL)
+            })?
+            .json::<PriceResponse>()
+            .await
+            .map_err(|e| {
+                Error::parse_error()
+                    .with_source(e)
+                    .with_method(PRICE_API_URL)
+            })?;
+
+        let prices = response
+            .data
+            .into_iter()
+            .filter_map(|(id, data)| Some((id, data?.price.parse().ok()?)))
+            .collect();
+
+        Ok(prices)
+    }
+}
+
+#[rocket::async_trait]
+impl PriceSource for JupiterPriceSource {
+    fn id(&self) -> &'static str {
+        "jupiter-price"
+    }
+
+    /// Prices are refreshed by Jupiter every few seconds
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    async fn get_price(
+        &self,
+        _client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        _decimals: u8,
+    ) -> Result<Option<f64>, Error> {
+        let prices = self.fetch_prices(&[mint.to_string()]).await?;
+        Ok(prices.get(&mint.to_string()).copied())
+    }
+
+    async fn get_prices(
+        &self,
+        _client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        let chunks: Vec<&[(Pubkey, u8)]> = mints.chunks(MAX_PRICE_IDS).collect();
+        let results = join_all(chunks.iter().map(|chunk| {
+            let ids: Vec<String> = chunk.iter().map(|(mint, _)| mint.to_string()).collect();
+            async move { self.fetch_prices(&ids).await }
+        }))
+        .await;
+
+        let mut prices = SourcePrices::default();
+        for (chunk, result) in chunks.into_iter().zip(results) {
+            match result {
+                Ok(chunk_prices) => {
+                    prices
+                        .prices
+                        .extend(chunk_prices.into_iter().filter_map(|(id, price)| {
+                            Some((Pubkey::from_str(&id).ok()?, price.into()))
+                        }))
+                }
+                Err(_) => prices.failed.extend(chunk.iter().map(|(mint, _)| *mint)),
+            }
+        }
+        prices
+    }
+}
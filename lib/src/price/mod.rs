@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::{
+    future::join,
+    stream::{self, StreamExt},
+};
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::client::SolanaMirrorClient;
+use crate::utils::get_oracle_source;
+use crate::{Error, USDC_ADDRESS};
+
+use cache::PriceCache;
+use coingecko::CoingeckoPriceSource;
+use jupiter::{JupiterPriceSource, JupiterQuoteSource};
+use pool::PoolPriceSource;
+
+pub mod cache;
+pub mod coingecko;
+pub mod jupiter;
+pub mod oracle;
+pub mod pool;
+
+/// Offset of the decimals in a mint account, same for SPL Token and Token-2022
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+/// Shared by every price source, so their connections are pooled together
+pub(crate) static HTTP_CLIENT: Lazy<Client> = Lazy::new(Client::new);
+
+/// Lookups of a source without a bulk endpoint that are in flight at once
+pub(crate) const MAX_CONCURRENT_LOOKUPS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PriceQuote {
+    /// USD price of a whole token
+    pub price: f64,
+    /// Id of the price source that answered
+    pub source: String,
+    /// Unix timestamp of the lookup, older than the request if it came from the cache
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: u64,
+    /// How much the price can be trusted, only known for sources that quote a swap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<PriceLiquidity>,
+}
+
+/// How deep the market behind a price is, read from the route of a quote
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PriceLiquidity {
+    /// Price impact of the quoted swap, as a fraction (0.01 is 1%)
+    #[serde(rename = "priceImpactPct")]
+    pub price_impact_pct: f64,
+    /// Pools the swap is routed through
+    #[serde(rename = "routeHops")]
+    pub route_hops: usize,
+    /// Between 0 and 1, lower for higher price impacts and longer routes
+    pub confidence: f64,
+}
+
+/// A price as answered by a source, before it's turned into a quote
+#[derive(Debug, Clone)]
+pub struct SourcePrice {
+    pub price: f64,
+    pub liquidity: Option<PriceLiquidity>,
+}
+
+impl From<f64> for SourcePrice {
+    fn from(price: f64) -> Self {
+        Self {
+            price,
+            liquidity: None,
+        }
+    }
+}
+
+/// What a source answered for a batch of tokens
+#[derive(Debug, Default)]
+pub struct SourcePrices {
+    pub prices: HashMap<Pubkey, SourcePrice>,
+    /// Tokens whose lookup failed, as opposed to the ones the source doesn't know
+    pub failed: HashSet<Pubkey>,
+}
+
+/// Collects the answers of a source that looks up one token at a time
+impl FromIterator<(Pubkey, Result<Option<SourcePrice>, Error>)> for SourcePrices {
+    fn from_iter<I: IntoIterator<Item = (Pubkey, Result<Option<SourcePrice>, Error>)>>(
+        iter: I,
+    ) -> Self {
+        let mut prices = Self::default();
+        for (mint, result) in iter {
+            match result {
+                Ok(Some(price)) => {
+                    prices.prices.insert(mint, price);
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    prices.failed.insert(mint);
+                }
+            }
+        }
+        prices
+    }
+}
+
+/// Prices of a batch of tokens, as answered by the chain
+#[derive(Debug, Default)]
+pub struct Prices {
+    pub quotes: HashMap<Pubkey, PriceQuote>,
+    /// Unpriced tokens some source failed to look up, so they may still have a price
+    pub failed: HashSet<Pubkey>,
+}
+
+impl PriceQuote {
+    pub fn new(price: f64, source: &str) -> Self {
+        Self {
+            price,
+            source: source.to_string(),
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            liquidity: None,
+        }
+    }
+
+    pub fn with_liquidity(mut self, liquidity: Option<PriceLiquidity>) -> Self {
+        self.liquidity = liquidity;
+        self
+    }
+}
+
+/// Somewhere the USD price of a token can be read from
+#[rocket::async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Key of the source in the `PRICE_SOURCES` env var, eg. `jupiter-quote`
+    fn id(&self) -> &'static str;
+
+    /// How long the prices of the source are cached for
+    fn ttl(&self) -> Duration {
+        DEFAULT_PRICE_TTL
+    }
+
+    /// Price of a whole token in USD, `None` if the source doesn't know the token
+    /// and an error if it couldn't be looked up
+    async fn get_price(
+        &self,
+        client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<Option<f64>, Error>;
+
+    /// Prices of several tokens, leaving out the ones the source doesn't know.
+    /// Sources with a bulk endpoint override it, the rest ask for a few tokens at a time
+    async fn get_prices(
+        &self,
+        client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        let price_futures = mints.iter().copied().map(|(mint, decimals)| async move {
+            let price = self.get_price(client, &mint, decimals).await;
+            (mint, price.map(|price| price.map(SourcePrice::from)))
+        });
+
+        stream::iter(price_futures)
+            .buffer_unordered(MAX_CONCURRENT_LOOKUPS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Asks each source in order until one of them knows the price,
+/// so an outage of one of them doesn't leave every token unpriced
+pub struct PriceChain {
+    sources: Vec<Box<dyn PriceSource>>,
+}
+
+impl PriceChain {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl PriceSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Builds the chain out of source ids, unknown ones are skipped
+    pub fn from_ids(ids: &[&str]) -> Self {
+        let mut chain = Self::new();
+        for id in ids {
+            chain = match *id {
+                "jupiter-quote" => chain.with_source(JupiterQuoteSource),
+                "jupiter-price" => chain.with_source(JupiterPriceSource),
+                "oracle" => chain.with_source(get_oracle_source()),
+                "coingecko" => chain.with_source(CoingeckoPriceSource::new()),
+                "pool" => chain.with_source(PoolPriceSource),
+                _ => {
+                    eprintln!("Unknown price source: {}", id);
+                    chain
+                }
+            };
+        }
+        chain
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &dyn PriceSource> {
+        self.sources.iter().map(|source| source.as_ref())
+    }
+
+    /// TTL of the source with the given id, the default one if it isn't in the chain
+    pub fn ttl(&self, source_id: &str) -> Duration {
+        self.sources()
+            .find(|source| source.id() == source_id)
+            .map_or(DEFAULT_PRICE_TTL, |source| source.ttl())
+    }
+
+    /// Only the tokens no source priced are passed on to the next one. A token left
+    /// unpriced counts as failed if any source failed to look it up
+    pub async fn get_prices(&self, client: &SolanaMirrorClient, mints: &[(Pubkey, u8)]) -> Prices {
+        let mut result = Prices::default();
+        let mut remaining = mints.to_vec();
+
+        for source in &self.sources {
+            if remaining.is_empty() {
+                break;
+            }
+
+            let SourcePrices { prices, failed } = source.get_prices(client, &remaining).await;
+            remaining.retain(|(mint, _)| !prices.contains_key(mint));
+            result.failed.extend(failed);
+            result
+                .quotes
+                .extend(prices.into_iter().map(|(mint, price)| {
+                    let quote = PriceQuote::new(price.price, source.id());
+                    (mint, quote.with_liquidity(price.liquidity))
+                }));
+        }
+
+        result
+            .failed
+            .retain(|mint| !result.quotes.contains_key(mint));
+        result
+    }
+}
+
+impl Default for PriceChain {
+    fn default() -> Self {
+        Self::from_ids(&DEFAULT_PRICE_SOURCES)
+    }
+}
+
+pub const DEFAULT_PRICE_TTL: Duration = Duration::from_secs(60);
+
+/// The bulk price API goes first, so only the tokens it doesn't know are quoted one by one.
+/// `pool` scans the Whirlpool program for each token, so it's opt-in through `PRICE_SOURCES`
+pub const DEFAULT_PRICE_SOURCES: [&str; 4] =
+    ["jupiter-price", "jupiter-quote", "oracle", "coingecko"];
+
+static PRICE_CHAIN: OnceCell<PriceChain> = OnceCell::new();
+
+static PRICE_CACHE: Lazy<PriceCache> = Lazy::new(PriceCache::default);
+
+/// Sets the chain used by `get_price`, only the first call has an effect.
+/// If it's never called the default chain is used
+pub fn set_price_chain(chain: PriceChain) {
+    if PRICE_CHAIN.set(chain).is_err() {
+        eprintln!("The price chain was already set");
+    }
+}
+
+pub fn get_price_chain() -> &'static PriceChain {
+    PRICE_CHAIN.get_or_init(PriceChain::default)
+}
+
+/// Gets the price of the mint against USDC
+/// Lets the caller pass the decimals beforehand. If they're not passed, they will be fetched
+pub async fn get_price(
+    client: &SolanaMirrorClient,
+    token: Pubkey,
+    decimals: Option<u8>,
+) -> Option<f64> {
+    let quotes = match decimals {
+        Some(decimals) => get_prices_with_decimals(client, &[(token, decimals)]).await,
+        None => get_prices(client, &[token]).await,
+    };
+
+    quotes.get(&token).map(|quote| quote.price)
+}
+
+/// Gets the prices of several mints at once, fetching all their decimals in a single batch.
+/// Mints without a price are left out of the map
+pub async fn get_prices(
+    client: &SolanaMirrorClient,
+    mints: &[Pubkey],
+) -> HashMap<Pubkey, PriceQuote> {
+    let mut unique_mints: Vec<Pubkey> = Vec::with_capacity(mints.len());
+    for mint in mints {
+        if !unique_mints.contains(mint) {
+            unique_mints.push(*mint);
+        }
+    }
+
+    let mint_accounts = match client.get_multiple_accounts(&unique_mints).await {
+        Ok(accounts) => accounts,
+        Err(_) => return HashMap::new(),
+    };
+
+    let mints_with_decimals: Vec<(Pubkey, u8)> = mint_accounts
+        .into_iter()
+        .flatten()
+        .filter_map(|account| {
+            let decimals = *account.data.get(MINT_DECIMALS_OFFSET)?;
+            Some((account.pubkey, decimals))
+        })
+        .collect();
+
+    get_prices_with_decimals(client, &mints_with_decimals).await
+}
+
+/// Same as `get_prices`, for callers that already know the decimals
+pub async fn get_prices_with_decimals(
+    client: &SolanaMirrorClient,
+    mints: &[(Pubkey, u8)],
+) -> HashMap<Pubkey, PriceQuote> {
+    lookup_prices(client, mints).await.quotes
+}
+
+/// Same as `get_prices_with_decimals`, also telling which of the unpriced mints
+/// couldn't be looked up, for the callers that treat unknown tokens differently
+pub async fn lookup_prices(client: &SolanaMirrorClient, mints: &[(Pubkey, u8)]) -> Prices {
+    let usdc = Pubkey::from_str(USDC_ADDRESS).unwrap();
+
+    let mut unique_mints: Vec<(Pubkey, u8)> = Vec::with_capacity(mints.len());
+    for (mint, decimals) in mints {
+        if *mint != usdc && !unique_mints.iter().any(|(m, _)| m == mint) {
+            unique_mints.push((*mint, *decimals));
+        }
+    }
+
+    let (quotes, pending, claim) = PRICE_CACHE.claim(&unique_mints);
+
+    let chain = get_price_chain();
+    let (fetched, waited) = join(
+        chain.get_prices(client, &claim.mints),
+        PriceCache::wait(pending),
+    )
+    .await;
+    PRICE_CACHE.complete(claim, &fetched, |quote| chain.ttl(&quote.source));
+
+    let mut prices = Prices {
+        quotes,
+        failed: HashSet::new(),
+    };
+    for lookup in [fetched, waited] {
+        prices.quotes.extend(lookup.quotes);
+        prices.failed.extend(lookup.failed);
+    }
+
+    // USDC is the quote currency, its price is 1 by definition
+    if mints.iter().any(|(mint, _)| *mint == usdc) {
+        prices.quotes.insert(usdc, PriceQuote::new(1.0, "usdc"));
+    }
+
+    prices
+}
@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    client::{GetAccountDataConfig, SolanaMirrorClient},
+    utils::{read_i128, read_i32, read_i64, read_u32, read_u64},
+    Error,
+};
+
+use super::{PriceSource, SourcePrice, SourcePrices};
+
+/// Program that owns the on-chain oracle accounts a mint is priced from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OracleKind {
+    /// Either a legacy price account or a `PriceUpdateV2` of the pull oracle
+    Pyth,
+    /// On-demand pull feed
+    Switchboard,
+}
+
+impl OracleKind {
+    pub fn new(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().as_str() {
+            "pyth" => Some(Self::Pyth),
+            "switchboard" => Some(Self::Switchboard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OracleFeed {
+    pub kind: OracleKind,
+    pub address: Pubkey,
+}
+
+/// A price as published by an oracle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OraclePrice {
+    pub price: f64,
+    /// Width of the confidence interval around the price, in the same unit
+    pub confidence: f64,
+    /// Unix timestamp of the update the price comes from
+    pub publish_time: i64,
+}
+
+/// Feeds of the majors, extended or overridden through the `ORACLE_FEEDS` env var
+pub const DEFAULT_ORACLE_FEEDS: [(&str, OracleKind, &str); 2] = [
+    // SOL/USD
+    (
+        "So11111111111111111111111111111111111111112",
+        OracleKind::Pyth,
+        "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE",
+    ),
+    // USDT/USD
+    (
+        "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
+        OracleKind::Pyth,
+        "HT2PLQBcG5EiCcNSaMHAjSgd9F98ecpATbk4Sk5oYuM",
+    ),
+];
+
+/// Older prices mean the feed stopped being updated
+const MAX_ORACLE_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Prices whose confidence interval is wider than this share of the price are discarded
+const MAX_CONFIDENCE_RATIO: f64 = 0.02;
+
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+const SWITCHBOARD_PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+/// Switchboard values are fixed point numbers with 18 decimals
+const SWITCHBOARD_PRECISION: i32 = 18;
+const SWITCHBOARD_RESULT_OFFSET: usize = 2264;
+const SWITCHBOARD_SUBMISSION_TIMESTAMPS_OFFSET: usize = 2952;
+
+/// Reads prices straight from Pyth and Switchboard accounts, so the majors
+/// keep a price when the HTTP APIs are down
+pub struct OraclePriceSource {
+    feeds: HashMap<Pubkey, OracleFeed>,
+}
+
+impl OraclePriceSource {
+    pub fn new(feeds: HashMap<Pubkey, OracleFeed>) -> Self {
+        Self { feeds }
+    }
+
+    pub fn with_feed(mut self, mint: Pubkey, feed: OracleFeed) -> Self {
+        self.feeds.insert(mint, feed);
+        self
+    }
+
+    /// Prices the mints from the given oracle accounts, leaving out the stale or unreliable ones
+    fn parse_prices(&self, accounts: &HashMap<Pubkey, Vec<u8>>) -> HashMap<Pubkey, SourcePrice> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        self.feeds
+            .iter()
+            .filter_map(|(mint, feed)| {
+                let data = accounts.get(&feed.address)?;
+                let price = decode_oracle_price(feed.kind, data)?;
+                is_reliable(&price, now).then(|| (*mint, price.price.into()))
+            })
+            .collect()
+    }
+}
+
+impl Default for OraclePriceSource {
+    fn default() -> Self {
+        let feeds = DEFAULT_ORACLE_FEEDS
+            .iter()
+            .map(|(mint, kind, address)| {
+                let feed = OracleFeed {
+                    kind: *kind,
+                    address: Pubkey::from_str(address).unwrap(),
+                };
+                (Pubkey::from_str(mint).unwrap(), feed)
+            })
+            .collect();
+
+        Self::new(feeds)
+    }
+}
+
+#[rocket::async_trait]
+impl PriceSource for OraclePriceSource {
+    fn id(&self) -> &'static str {
+        "oracle"
+    }
+
+    /// Feeds are updated every few seconds
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    async fn get_price(
+        &self,
+        client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        _decimals: u8,
+    ) -> Result<Option<f64>, Error> {
+        let Some(feed) = self.feeds.get(mint) else {
+            return Ok(None);
+        };
+        let data = client
+            .get_account_info(
+                &feed.address,
+                Some(GetAccountDataConfig {
+                    commitment: None,
+                    encoding: Some("base64".to_string()),
+                }),
+            )
+            .await?;
+
+        let accounts = HashMap::from([(feed.address, data)]);
+        Ok(self
+            .parse_prices(&accounts)
+            .get(mint)
+            .map(|price| price.price))
+    }
+
+    async fn get_prices(
+        &self,
+        client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        let fed_mints: Vec<Pubkey> = mints
+            .iter()
+            .map(|(mint, _)| *mint)
+            .filter(|mint| self.feeds.contains_key(mint))
+            .collect();
+        let addresses: Vec<Pubkey> = fed_mints
+            .iter()
+            .map(|mint| self.feeds[mint].address)
+            .collect();
+
+        if addresses.is_empty() {
+            return SourcePrices::default();
+        }
+
+        let Ok(accounts) = client.get_multiple_accounts(&addresses).await else {
+            return SourcePrices {
+                prices: HashMap::new(),
+                failed: fed_mints.into_iter().collect(),
+            };
+        };
+
+        let accounts: HashMap<Pubkey, Vec<u8>> = accounts
+            .into_iter()
+            .flatten()
+            .map(|account| (account.pubkey, account.data))
+            .collect();
+
+        let mut prices = self.parse_prices(&accounts);
+        prices.retain(|mint, _| fed_mints.contains(mint));
+        SourcePrices {
+            prices,
+            failed: Default::default(),
+        }
+    }
+}
+
+/// Decodes the price stored in the account of a feed, `None` if it isn't a price account
+pub fn decode_oracle_price(kind: OracleKind, data: &[u8]) -> Option<OraclePrice> {
+    match kind {
+        OracleKind::Pyth if data.get(..8) == Some(&PYTH_PRICE_UPDATE_DISCRIMINATOR) => {
+            decode_pyth_price_update(data)
+        }
+        OracleKind::Pyth => decode_pyth_price_account(data),
+        OracleKind::Switchboard => decode_switchboard_pull_feed(data),
+    }
+}
+
+/// Legacy price account, only the aggregate of a trading feed is read
+pub fn decode_pyth_price_account(data: &[u8]) -> Option<OraclePrice> {
+    if read_u32(data, 0)? != PYTH_MAGIC
+        || read_u32(data, 4)? != PYTH_VERSION
+        || read_u32(data, 8)? != PYTH_PRICE_ACCOUNT_TYPE
+        || read_u32(data, 224)? != PYTH_STATUS_TRADING
+    {
+        return None;
+    }
+
+    let exponent = read_i32(data, 20)?;
+    let publish_time = read_i64(data, 96)?;
+    let price = read_i64(data, 208)?;
+    let confidence = read_u64(data, 216)?;
+
+    Some(OraclePrice {
+        price: price as f64 * 10_f64.powi(exponent),
+        confidence: confidence as f64 * 10_f64.powi(exponent),
+        publish_time,
+    })
+}
+
+/// `PriceUpdateV2` account written by the Pyth receiver program
+pub fn decode_pyth_price_update(data: &[u8]) -> Option<OraclePrice> {
+    if data.get(..8)? != PYTH_PRICE_UPDATE_DISCRIMINATOR {
+        return None;
+    }
+
+    // The verification level is an enum, `Partial` carries the number of signatures
+    let message_offset = match *data.get(40)? {
+        0 => 42,
+        1 => 41,
+        _ => return None,
+    };
+
+    // The message starts with the 32 bytes id of the feed
+    let price = read_i64(data, message_offset + 32)?;
+    let confidence = read_u64(data, message_offset + 40)?;
+    let exponent = read_i32(data, message_offset + 48)?;
+    let publish_time = read_i64(data, message_offset + 52)?;
+
+    Some(OraclePrice {
+        price: price as f64 * 10_f64.powi(exponent),
+        confidence: confidence as f64 * 10_f64.powi(exponent),
+        publish_time,
+    })
+}
+
+/// On-demand pull feed, the confidence is the standard deviation of the oracle submissions
+pub fn decode_switchboard_pull_feed(data: &[u8]) -> Option<OraclePrice> {
+    if data.get(..8)? != SWITCHBOARD_PULL_FEED_DISCRIMINATOR {
+        return None;
+    }
+
+    let result = SWITCHBOARD_RESULT_OFFSET;
+    // A feed that never got a result has its slot at 0
+    if read_u64(data, result + 104)? == 0 {
+        return None;
+    }
+
+    let value = read_i128(data, result)?;
+    let std_dev = read_i128(data, result + 16)?;
+    let submission_idx = *data.get(result + 97)? as usize;
+    let publish_time = read_i64(
+        data,
+        SWITCHBOARD_SUBMISSION_TIMESTAMPS_OFFSET + submission_idx * 8,
+    )?;
+
+    let scale = 10_f64.powi(-SWITCHBOARD_PRECISION);
+    Some(OraclePrice {
+        price: value as f64 * scale,
+        confidence: std_dev as f64 * scale,
+        publish_time,
+    })
+}
+
+/// Fresh, positive, and with a confidence interval narrow enough to value a balance with
+fn is_reliable(price: &OraclePrice, now: i64) -> bool {
+    price.price > 0.0
+        && now - price.publish_time <= MAX_ORACLE_AGE.as_secs() as i64
+        && price.confidence / price.price <= MAX_CONFIDENCE_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_730_000_000;
+
+    fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Legacy SOL/USD price account, trading at 150.12345 +- 0.015
+    fn pyth_price_account() -> Vec<u8> {
+        let mut data = vec![0; 3312];
+        write(&mut data, 0, &PYTH_MAGIC.to_le_bytes());
+        write(&mut data, 4, &PYTH_VERSION.to_le_bytes());
+        write(&mut data, 8, &PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        write(&mut data, 20, &(-8_i32).to_le_bytes());
+        write(&mut data, 96, &NOW.to_le_bytes());
+        write(&mut data, 208, &15_012_345_000_i64.to_le_bytes());
+        write(&mut data, 216, &1_500_000_u64.to_le_bytes());
+        write(&mut data, 224, &PYTH_STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    /// `PriceUpdateV2` with the given verification level, 150.12345 +- 0.015
+    fn pyth_price_update(verification_level: &[u8]) -> Vec<u8> {
+        let mut data = vec![0; 134];
+        write(&mut data, 0, &PYTH_PRICE_UPDATE_DISCRIMINATOR);
+        write(&mut data, 40, verification_level);
+
+        let message = 40 + verification_level.len();
+        write(&mut data, message, &[7; 32]);
+        write(&mut data, message + 32, &15_012_345_000_i64.to_le_bytes());
+        write(&mut data, message + 40, &1_500_000_u64.to_le_bytes());
+        write(&mut data, message + 48, &(-8_i32).to_le_bytes());
+        write(&mut data, message + 52, &NOW.to_le_bytes());
+        data
+    }
+
+    /// Pull feed at 150.5 +- 0.25, last updated by the third submission
+    fn switchboard_pull_feed() -> Vec<u8> {
+        let mut data = vec![0; 3208];
+        let result = SWITCHBOARD_RESULT_OFFSET;
+        write(&mut data, 0, &SWITCHBOARD_PULL_FEED_DISCRIMINATOR);
+        write(
+            &mut data,
+            result,
+            &150_500_000_000_000_000_000_i128.to_le_bytes(),
+        );
+        write(
+            &mut data,
+            result + 16,
+            &250_000_000_000_000_000_i128.to_le_bytes(),
+        );
+        write(&mut data, result + 97, &[2]);
+        write(&mut data, result + 104, &300_000_000_u64.to_le_bytes());
+        write(
+            &mut data,
+            SWITCHBOARD_SUBMISSION_TIMESTAMPS_OFFSET + 2 * 8,
+            &NOW.to_le_bytes(),
+        );
+        data
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn decodes_legacy_pyth_price_account() {
+        let price = decode_oracle_price(OracleKind::Pyth, &pyth_price_account()).unwrap();
+        assert_close(price.price, 150.12345);
+        assert_close(price.confidence, 0.015);
+        assert_eq!(price.publish_time, NOW);
+    }
+
+    #[test]
+    fn skips_legacy_pyth_accounts_that_arent_trading() {
+        let mut data = pyth_price_account();
+        write(&mut data, 224, &0_u32.to_le_bytes());
+        assert_eq!(decode_pyth_price_account(&data), None);
+
+        let mut data = pyth_price_account();
+        write(&mut data, 8, &1_u32.to_le_bytes());
+        assert_eq!(decode_pyth_price_account(&data), None);
+
+        assert_eq!(
+            decode_pyth_price_account(&pyth_price_account()[..100]),
+            None
+        );
+    }
+
+    #[test]
+    fn decodes_pyth_price_update_at_every_verification_level() {
+        for level in [&[0, 5][..], &[1][..]] {
+            let data = pyth_price_update(level);
+            let price = decode_oracle_price(OracleKind::Pyth, &data).unwrap();
+            assert_close(price.price, 150.12345);
+            assert_close(price.confidence, 0.015);
+            assert_eq!(price.publish_time, NOW);
+        }
+
+        assert_eq!(decode_pyth_price_update(&pyth_price_update(&[2])), None);
+    }
+
+    #[test]
+    fn decodes_switchboard_pull_feed() {
+        let price = decode_oracle_price(OracleKind::Switchboard, &switchboard_pull_feed()).unwrap();
+        assert_close(price.price, 150.5);
+        assert_close(price.confidence, 0.25);
+        assert_eq!(price.publish_time, NOW);
+    }
+
+    #[test]
+    fn skips_switchboard_feeds_without_a_result() {
+        let mut data = switchboard_pull_feed();
+        write(
+            &mut data,
+            SWITCHBOARD_RESULT_OFFSET + 104,
+            &0_u64.to_le_bytes(),
+        );
+        assert_eq!(decode_switchboard_pull_feed(&data), None);
+
+        let mut data = switchboard_pull_feed();
+        write(&mut data, 0, &PYTH_PRICE_UPDATE_DISCRIMINATOR);
+        assert_eq!(decode_switchboard_pull_feed(&data), None);
+    }
+
+    #[test]
+    fn rejects_stale_prices() {
+        let max_age = MAX_ORACLE_AGE.as_secs() as i64;
+        let price = OraclePrice {
+            price: 150.0,
+            confidence: 0.1,
+            publish_time: NOW,
+        };
+
+        assert!(is_reliable(&price, NOW + max_age));
+        assert!(!is_reliable(&price, NOW + max_age + 1));
+    }
+
+    #[test]
+    fn rejects_wide_confidence_intervals() {
+        let price = OraclePrice {
+            price: 150.0,
+            confidence: 150.0 * MAX_CONFIDENCE_RATIO,
+            publish_time: NOW,
+        };
+        assert!(is_reliable(&price, NOW));
+
+        let price = OraclePrice {
+            confidence: 150.0 * MAX_CONFIDENCE_RATIO * 1.01,
+            ..price
+        };
+        assert!(!is_reliable(&price, NOW));
+
+        let price = OraclePrice {
+            price: 0.0,
+            confidence: 0.0,
+            ..price
+        };
+        assert!(!is_reliable(&price, NOW));
+    }
+}
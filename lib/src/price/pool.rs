@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::{
+    future::try_join,
+    stream::{self, StreamExt},
+};
+use once_cell::sync::Lazy;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    balances::dapps::orca::{types::Whirlpool, ORCA_WHIRLPOOL_PROGRAM_ID},
+    client::{ProgramAccountsFilter, SolanaMirrorClient},
+    utils::get_sqrt_price_from_sqrt_price_x64,
+    Error, USDC_ADDRESS,
+};
+
+use super::{PriceSource, SourcePrice, SourcePrices, MAX_CONCURRENT_LOOKUPS};
+
+const WHIRLPOOL_LEN: u64 = 653;
+const WHIRLPOOL_MINT_A_OFFSET: usize = 101;
+const WHIRLPOOL_MINT_B_OFFSET: usize = 181;
+const USDC_DECIMALS: i32 = 6;
+
+/// Tokens looked up per request, each one costs two scans of the Whirlpool program.
+/// The rest count as failed, so they aren't remembered as unknown
+const MAX_POOL_LOOKUPS: usize = 10;
+
+/// How long a token whose scans failed is skipped, many RPCs reject scanning the program
+const FAILED_LOOKUP_TTL: Duration = Duration::from_secs(60);
+
+/// Tokens whose scans failed, with the time until which they're skipped
+static FAILED_LOOKUPS: Lazy<Mutex<HashMap<Pubkey, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Reads the price from the deepest Orca Whirlpool pairing the token with USDC.
+/// Works for tokens no aggregator routes, but it's only as good as the pool liquidity.
+/// Scanning the program is expensive, so it's only used if listed in `PRICE_SOURCES`
+pub struct PoolPriceSource;
+
+#[rocket::async_trait]
+impl PriceSource for PoolPriceSource {
+    fn id(&self) -> &'static str {
+        "pool"
+    }
+
+    /// Each lookup scans the Whirlpool program, so it is worth caching for a bit
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    async fn get_price(
+        &self,
+        client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        decimals: u8,
+    ) -> Result<Option<f64>, Error> {
+        let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_PROGRAM_ID).unwrap();
+        let usdc = Pubkey::from_str(USDC_ADDRESS).unwrap();
+
+        let get_pools = |mint_a: &Pubkey, mint_b: &Pubkey| {
+            client.get_program_accounts(
+                &program_id,
+                vec![
+                    ProgramAccountsFilter::DataSize(WHIRLPOOL_LEN),
+                    ProgramAccountsFilter::memcmp(WHIRLPOOL_MINT_A_OFFSET, mint_a),
+                    ProgramAccountsFilter::memcmp(WHIRLPOOL_MINT_B_OFFSET, mint_b),
+                ],
+                None,
+            )
+        };
+
+        // The token can be on either side of the pair
+        let (base_pools, quote_pools) =
+            try_join(get_pools(mint, &usdc), get_pools(&usdc, mint)).await?;
+
+        let pools = base_pools
+            .iter()
+            .map(|pool| (pool, true))
+            .chain(quote_pools.iter().map(|pool| (pool, false)))
+            .filter_map(|(pool, is_base)| {
+                bincode::deserialize::<Whirlpool>(&pool.data)
+                    .ok()
+                    .map(|whirlpool| (whirlpool, is_base))
+            })
+            .filter(|(whirlpool, _)| whirlpool.liquidity > 0);
+
+        let Some((whirlpool, is_base)) = pools.max_by_key(|(whirlpool, _)| whirlpool.liquidity)
+        else {
+            return Ok(None);
+        };
+
+        // Price of token A in token B, in base units
+        let sqrt_price = get_sqrt_price_from_sqrt_price_x64(whirlpool.sqrt_price);
+        let raw_price = sqrt_price * sqrt_price;
+
+        let price = if is_base {
+            raw_price * 10_f64.powi(decimals as i32 - USDC_DECIMALS)
+        } else {
+            1.0 / (raw_price * 10_f64.powi(USDC_DECIMALS - decimals as i32))
+        };
+
+        Ok(price.is_finite().then_some(price))
+    }
+
+    /// Looks up a few tokens per request, skipping the ones that failed recently
+    async fn get_prices(
+        &self,
+        client: &SolanaMirrorClient,
+        mints: &[(Pubkey, u8)],
+    ) -> SourcePrices {
+        let now = Instant::now();
+        let (skipped, to_look_up): (Vec<(Pubkey, u8)>, Vec<(Pubkey, u8)>) = {
+            let mut failed_lookups = FAILED_LOOKUPS.lock().unwrap();
+            failed_lookups.retain(|_, skip_until| *skip_until > now);
+            mints
+                .iter()
+                .partition(|(mint, _)| failed_lookups.contains_key(mint))
+        };
+
+        let (to_look_up, over_limit) = to_look_up.split_at(to_look_up.len().min(MAX_POOL_LOOKUPS));
+
+        let price_futures = to_look_up
+            .iter()
+            .copied()
+            .map(|(mint, decimals)| async move {
+                let price = self.get_price(client, &mint, decimals).await;
+                (mint, price.map(|price| price.map(SourcePrice::from)))
+            });
+        let results: Vec<(Pubkey, Result<Option<SourcePrice>, Error>)> =
+            stream::iter(price_futures)
+                .buffer_unordered(MAX_CONCURRENT_LOOKUPS)
+                .collect()
+                .await;
+
+        let mut prices: SourcePrices = results.into_iter().collect();
+
+        let mut failed_lookups = FAILED_LOOKUPS.lock().unwrap();
+        for mint in &prices.failed {
+            failed_lookups.insert(*mint, now + FAILED_LOOKUP_TTL);
+        }
+
+        prices
+            .failed
+            .extend(skipped.iter().chain(over_limit).map(|(mint, _)| *mint));
+        prices
+    }
+}
@@ -168,9 +168,7 @@ fn parse_transaction(tx: &Transaction, signer: &Pubkey) -> Result<ParsedTransact
         .collect();
 
     for pre_balance in pre_token_balances {
-        let balance_change = balances
-            .entry(pre_balance.mint)
-            .or_insert(BalanceChange::default());
+        let balance_change = balances.entry(pre_balance.mint).or_default();
 
         balance_change.pre = FormattedAmount {
             amount: pre_balance.ui_token_amount.amount,
@@ -179,9 +177,7 @@ fn parse_transaction(tx: &Transaction, signer: &Pubkey) -> Result<ParsedTransact
     }
 
     for post_balance in post_token_balances {
-        let balance_change = balances
-            .entry(post_balance.mint)
-            .or_insert(BalanceChange::default());
+        let balance_change = balances.entry(post_balance.mint).or_default();
 
         balance_change.post = FormattedAmount {
             amount: post_balance.ui_token_amount.amount,
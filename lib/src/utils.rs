@@ -7,7 +7,16 @@ use solana_sdk::pubkey::Pubkey;
 
 use crate::{
     balances::accounts::types::{ImageResponse, ParsedMetadata},
-    client::{GetAccountDataConfig, SolanaMirrorClient},
+    client::{
+        failover::{Endpoint, Strategy},
+        limiter::RateLimit,
+        types::{MintInfo, TokenExtension},
+        GetAccountDataConfig, SolanaMirrorClient,
+    },
+    price::{
+        oracle::{OracleFeed, OracleKind, OraclePriceSource},
+        PriceChain,
+    },
     Error, Page, SOL_IMAGE, USDC_IMAGE,
 };
 
@@ -15,15 +24,106 @@ pub fn clean_string(s: String) -> String {
     s.trim_matches('\0').trim_matches('"').to_string()
 }
 
-pub fn get_rpc() -> String {
-    env::var("RPC").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string())
+/// Reads the RPC endpoints from the `RPC` env var, a comma separated list
+/// of urls with an optional weight each (eg. `https://a.com|3,https://b.com`).
+/// Weights must be positive integers, invalid ones are logged and replaced by 1
+pub fn get_rpc_endpoints() -> Vec<Endpoint> {
+    let rpc = env::var("RPC").unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string());
+
+    rpc.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.rsplit_once('|') {
+            Some((url, weight)) => Endpoint {
+                url: url.to_string(),
+                weight: match weight.trim().parse() {
+                    Ok(weight) if weight > 0 => weight,
+                    _ => {
+                        eprintln!("Invalid RPC weight for {}: {}", url, weight);
+                        1
+                    }
+                },
+            },
+            None => Endpoint {
+                url: entry.to_string(),
+                weight: 1,
+            },
+        })
+        .collect()
 }
 
-pub fn create_batches<T: Clone>(
-    arr: &Vec<T>,
-    batch_size: usize,
-    limit: Option<u32>,
-) -> Vec<Vec<T>> {
+/// Reads a rate limit from the `<PREFIX>_RATE_LIMIT` (requests per second) and
+/// `<PREFIX>_MAX_IN_FLIGHT` env vars, falling back to `default` for the missing ones
+pub fn get_rate_limit(prefix: &str, default: RateLimit) -> RateLimit {
+    let requests_per_second = env::var(format!("{}_RATE_LIMIT", prefix))
+        .ok()
+        .and_then(|rps| rps.parse::<f64>().ok())
+        .filter(|rps| *rps > 0.0);
+    let max_in_flight = env::var(format!("{}_MAX_IN_FLIGHT", prefix))
+        .ok()
+        .and_then(|max| max.parse::<usize>().ok());
+
+    RateLimit {
+        // Allow up to a second worth of requests in a burst
+        burst: requests_per_second.map_or(default.burst, |rps| rps.ceil().max(1.0) as u32),
+        requests_per_second: requests_per_second.unwrap_or(default.requests_per_second),
+        max_in_flight: max_in_flight.unwrap_or(default.max_in_flight),
+    }
+}
+
+/// Reads how requests are spread over the RPC endpoints from the `RPC_STRATEGY` env var
+pub fn get_rpc_strategy() -> Strategy {
+    env::var("RPC_STRATEGY")
+        .ok()
+        .and_then(|strategy| Strategy::new(&strategy))
+        .unwrap_or(Strategy::Health)
+}
+
+/// Reads the order in which prices are looked up from the `PRICE_SOURCES` env var,
+/// a comma separated list of source ids (eg. `jupiter-price,coingecko,pool`)
+pub fn get_price_sources() -> PriceChain {
+    match env::var("PRICE_SOURCES") {
+        Ok(sources) => {
+            let ids: Vec<&str> = sources
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .collect();
+            PriceChain::from_ids(&ids)
+        }
+        Err(_) => PriceChain::default(),
+    }
+}
+
+/// Reads the oracle feeds of each mint from the `ORACLE_FEEDS` env var, a comma separated
+/// list of `<mint>=<pyth|switchboard>:<account>` entries. They're added to the default feeds,
+/// replacing the default one of the same mint
+pub fn get_oracle_source() -> OraclePriceSource {
+    let mut source = OraclePriceSource::default();
+    let Ok(feeds) = env::var("ORACLE_FEEDS") else {
+        return source;
+    };
+
+    for entry in feeds.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=').and_then(|(mint, feed)| {
+            let (kind, address) = feed.split_once(':')?;
+            let feed = OracleFeed {
+                kind: OracleKind::new(kind.trim())?,
+                address: Pubkey::from_str(address.trim()).ok()?,
+            };
+            Some((Pubkey::from_str(mint.trim()).ok()?, feed))
+        });
+
+        match parsed {
+            Some((mint, feed)) => source = source.with_feed(mint, feed),
+            None => eprintln!("Invalid oracle feed: {}", entry),
+        }
+    }
+
+    source
+}
+
+pub fn create_batches<T: Clone>(arr: &[T], batch_size: usize, limit: Option<u32>) -> Vec<Vec<T>> {
     let mut batches: Vec<Vec<T>> = Vec::new();
     let mut total_elements = 0;
 
@@ -80,13 +180,24 @@ static METADATA_CACHE: Lazy<Mutex<HashMap<String, ParsedMetadata>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
 /// Fetches or retrieves from cache the metadata associated with the given SPL token mint address.
-pub async fn fetch_metadata(client: &SolanaMirrorClient, mint_address: &str) -> ParsedMetadata {
+/// Token-2022 mints pass their mint info, whose embedded metadata is preferred over a Metaplex account
+pub async fn fetch_metadata(
+    client: &SolanaMirrorClient,
+    mint_address: &str,
+    mint_info: Option<&MintInfo>,
+) -> ParsedMetadata {
+    if let Some(metadata) = mint_info.and_then(get_embedded_metadata) {
+        return metadata;
+    }
+
     let mut cache = METADATA_CACHE.lock().await;
     if let Some(metadata) = cache.get(mint_address) {
         return metadata.clone();
     }
 
-    let mint_pubkey = Pubkey::from_str(mint_address).unwrap();
+    let Ok(mint_pubkey) = Pubkey::from_str(mint_address) else {
+        return ParsedMetadata::default();
+    };
     let mpl_program_id = Pubkey::from_str(MPL_TOKEN_METADATA_ID.to_string().as_str()).unwrap();
 
     // Get the metadata account address associated with the mint
@@ -99,7 +210,7 @@ pub async fn fetch_metadata(client: &SolanaMirrorClient, mint_address: &str) ->
         &mpl_program_id,
     );
 
-    let data = match client
+    let metaplex_metadata = match client
         .get_account_info(
             &metadata_pubkey,
             Some(GetAccountDataConfig {
@@ -109,15 +220,13 @@ pub async fn fetch_metadata(client: &SolanaMirrorClient, mint_address: &str) ->
         )
         .await
     {
-        Ok(data) => data,
+        Ok(data) => Metadata::safe_deserialize(&data).ok().map(parse_metadata),
+        // A missing account fails to parse, anything else is worth retrying on the next call
+        Err(Error::ParseError(_)) => None,
         Err(_) => return ParsedMetadata::default(),
     };
 
-    let parsed_metadata = match Metadata::safe_deserialize(&data) {
-        Ok(metadata) => parse_metadata(metadata),
-        Err(_) => ParsedMetadata::default(),
-    };
-
+    let parsed_metadata = metaplex_metadata.unwrap_or_default();
     cache.insert(mint_address.to_string(), parsed_metadata.clone());
     parsed_metadata
 }
@@ -131,6 +240,21 @@ fn parse_metadata(metadata: Metadata) -> ParsedMetadata {
     }
 }
 
+/// Parses the metadata embedded in a Token-2022 mint through the token metadata extension.
+pub fn get_embedded_metadata(mint_info: &MintInfo) -> Option<ParsedMetadata> {
+    mint_info
+        .extensions
+        .iter()
+        .find_map(|extension| match extension {
+            TokenExtension::TokenMetadata(token_metadata) => Some(ParsedMetadata {
+                name: clean_string(token_metadata.name.clone()),
+                symbol: clean_string(token_metadata.symbol.clone()),
+                uri: clean_string(token_metadata.uri.clone()),
+            }),
+            _ => None,
+        })
+}
+
 static IMAGE_CACHE: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
 pub async fn fetch_image(metadata: &ParsedMetadata) -> String {
@@ -199,8 +323,13 @@ pub fn calculate_concentrated_liquidity_amounts(
     }
 }
 
+/// Price of token A in token B at the given tick, in base units
+pub fn get_price_from_tick(tick: i32) -> f64 {
+    1.0001f64.powi(tick)
+}
+
 pub fn get_sqrt_price_from_tick(tick: i32) -> f64 {
-    (1.0001f64.powi(tick) as f64).sqrt()
+    1.0001f64.powi(tick).sqrt()
 }
 
 pub fn get_sqrt_price_from_sqrt_price_x64(sqrt_price_x64: u128) -> f64 {
@@ -227,3 +356,73 @@ pub fn calculate_tokens_in_range(
     let amount_b = liquidity * (sqrt_price_current - sqrt_price_lower);
     (amount_a, amount_b)
 }
+
+/// Computes `a * b >> 64` without overflowing, like the `mul_div_floor(a, b, Q64)`
+/// the CLMM programs use to turn a Q64.64 fee or reward growth into an amount.
+/// Results that don't fit a u64 are reported as 0, matching how the programs underflow them
+pub fn mul_shift_64(a: u128, b: u128) -> u64 {
+    let mask = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & mask);
+    let (b_hi, b_lo) = (b >> 64, b & mask);
+
+    // Anything above 2^128 before the shift can't fit a u64 after it
+    if a_hi != 0 && b_hi != 0 {
+        return 0;
+    }
+
+    let result = (a_hi * b_lo)
+        .checked_add(a_lo * b_hi)
+        .and_then(|sum| sum.checked_add((a_lo * b_lo) >> 64));
+
+    result
+        .and_then(|result| u64::try_from(result).ok())
+        .unwrap_or(0)
+}
+
+/// Reads a little endian u16 at the given offset, `None` if the data is too short
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian u32 at the given offset, `None` if the data is too short
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian i32 at the given offset, `None` if the data is too short
+pub fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian u64 at the given offset, `None` if the data is too short
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian i64 at the given offset, `None` if the data is too short
+pub fn read_i64(data: &[u8], offset: usize) -> Option<i64> {
+    data.get(offset..offset + 8)
+        .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian i128 at the given offset, `None` if the data is too short
+pub fn read_i128(data: &[u8], offset: usize) -> Option<i128> {
+    data.get(offset..offset + 16)
+        .map(|bytes| i128::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a little endian u128 at the given offset, `None` if the data is too short
+pub fn read_u128(data: &[u8], offset: usize) -> Option<u128> {
+    data.get(offset..offset + 16)
+        .map(|bytes| u128::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Reads a pubkey at the given offset, `None` if the data is too short
+pub fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    data.get(offset..offset + 32)
+        .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use lib::{
+    balances::accounts::{get_parsed_accounts, TOKEN_2022_PROGRAM_ID},
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    SOL_ADDRESS, USDC_ADDRESS,
+};
+use serde_json::json;
+
+use common::{client, fixture, owner, use_fixed_prices, BONK, BONK_PRICE, SOL_PRICE};
+
+fn transport() -> FixtureTransport {
+    FixtureTransport::new()
+        .with_result_containing(
+            JsonRpcMethod::GetTokenAccountsByOwner,
+            TOKEN_2022_PROGRAM_ID,
+            json!({ "context": { "apiVersion": "2.0.15", "slot": 300000000 }, "value": [] }),
+        )
+        .with_result(
+            JsonRpcMethod::GetTokenAccountsByOwner,
+            fixture("token_accounts"),
+        )
+        .with_result(JsonRpcMethod::GetMultipleAccounts, fixture("mints"))
+        .with_result(JsonRpcMethod::GetBalance, fixture("balance"))
+}
+
+#[rocket::async_test]
+async fn parses_token_accounts_and_sol() {
+    use_fixed_prices();
+    let client = client(transport());
+
+    let accounts = get_parsed_accounts(&client, &owner()).await.unwrap();
+    assert_eq!(accounts.len(), 3);
+
+    let usdc = accounts.iter().find(|a| a.mint == USDC_ADDRESS).unwrap();
+    assert_eq!(usdc.decimals, 6);
+    assert_eq!(usdc.balance.amount, "12500000");
+    assert_eq!(usdc.balance.formatted, 12.5);
+    assert_eq!(usdc.price, Some(1.0));
+    assert_eq!(usdc.value_usd, Some(12.5));
+    assert_eq!(usdc.program, "spl-token");
+
+    let bonk = accounts.iter().find(|a| a.mint == BONK).unwrap();
+    assert_eq!(bonk.balance.formatted, 1_000_000.0);
+    assert_eq!(bonk.price, Some(BONK_PRICE));
+    assert_eq!(bonk.price_quote.as_ref().unwrap().source, "fixed");
+    assert!(!bonk.is_lst);
+
+    let sol = accounts.iter().find(|a| a.mint == SOL_ADDRESS).unwrap();
+    assert_eq!(sol.balance.amount, "2500000000");
+    assert_eq!(sol.value_usd, Some(2.5 * SOL_PRICE));
+    assert_eq!(sol.program, "native");
+}
+
+#[rocket::async_test]
+async fn fails_when_the_token_accounts_cant_be_fetched() {
+    use_fixed_prices();
+    let client = client(FixtureTransport::new().with_error(
+        JsonRpcMethod::GetTokenAccountsByOwner,
+        -32005,
+        "Node is behind",
+    ));
+
+    assert!(get_parsed_accounts(&client, &owner()).await.is_err());
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lib::{
+    chart::{get_chart_data, Timeframe},
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    coingecko::CoingeckoClient,
+    SOL_ADDRESS,
+};
+use serde_json::Value;
+
+use common::{
+    client, fixture, owner, use_fixed_prices, BONK, BONK_PRICE, SIGNATURE_RECEIVE, SIGNATURE_SEND,
+    SOL_PRICE,
+};
+
+const DAY: i64 = 86400;
+
+/// The chart only covers the last days, so the captured transactions are moved next to now
+fn transaction(name: &str, block_time: i64) -> Value {
+    let mut tx = fixture(name);
+    tx["blockTime"] = block_time.into();
+    tx
+}
+
+#[rocket::async_test]
+async fn values_the_last_state_at_current_prices() {
+    use_fixed_prices();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let transport = FixtureTransport::new()
+        .with_result(
+            JsonRpcMethod::GetSignaturesForAddress,
+            fixture("signatures"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetTransaction,
+            SIGNATURE_RECEIVE,
+            transaction("transaction_receive", now - 5 * DAY),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetTransaction,
+            SIGNATURE_SEND,
+            transaction("transaction_send", now - 2 * DAY),
+        )
+        .with_result(JsonRpcMethod::GetMultipleAccounts, fixture("mints"));
+    let client = client(transport);
+
+    let states = get_chart_data(
+        &client,
+        &CoingeckoClient::new(),
+        &owner(),
+        Timeframe::Day,
+        7,
+    )
+    .await
+    .unwrap();
+
+    assert!(!states.is_empty());
+    assert!(states.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+    let last = states.last().unwrap();
+    assert_eq!(last.balances[SOL_ADDRESS].price, SOL_PRICE);
+    assert_eq!(last.balances[BONK].price, BONK_PRICE);
+
+    let expected: f64 = last
+        .balances
+        .values()
+        .map(|balance| balance.amount.formatted * balance.price)
+        .sum();
+    assert!(last.usd_value > 0.0);
+    assert!((last.usd_value - expected).abs() < 1e-9);
+
+    // There are no historical prices for the fixture mints
+    assert!(states[..states.len() - 1]
+        .iter()
+        .all(|state| state.usd_value == 0.0));
+}
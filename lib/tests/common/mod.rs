@@ -0,0 +1,68 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Once;
+
+use lib::{
+    client::SolanaMirrorClient,
+    price::{set_price_chain, PriceChain, PriceSource},
+};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+pub const OWNER: &str = "3Wga6r7yBhshmEnNwTScK1GsAyE5ZkoV6TiheQAhEs6D";
+pub const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+pub const SIGNATURE_RECEIVE: &str =
+    "XLwzmzdyPyzaj1UMQ8DsdB2scdF18T3HHfBmD8LKxYXChzwPvQwGRWHk2fAhvM7rjfxksmSkLxFoSkmS7K48sSj";
+pub const SIGNATURE_SEND: &str =
+    "2dg13SEjq2sHTdQUwCyFJ4TrComWtnDprNVPrtbvN9SmQPPu33cdsQVLDPjaYgebQr2LSRsVerQuDh2RuQur3GLM";
+
+pub const SOL_PRICE: f64 = 150.0;
+pub const BONK_PRICE: f64 = 0.00002;
+
+/// Reads a JSON-RPC result captured under `tests/fixtures`
+pub fn fixture(name: &str) -> Value {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let contents = std::fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&contents).unwrap()
+}
+
+pub fn owner() -> Pubkey {
+    Pubkey::from_str(OWNER).unwrap()
+}
+
+pub fn client(transport: lib::client::transport::FixtureTransport) -> SolanaMirrorClient {
+    SolanaMirrorClient::from_transport(transport)
+}
+
+/// Prices from a fixed table, so the tests don't reach any price API
+pub struct FixedPriceSource;
+
+#[rocket::async_trait]
+impl PriceSource for FixedPriceSource {
+    fn id(&self) -> &'static str {
+        "fixed"
+    }
+
+    async fn get_price(
+        &self,
+        _client: &SolanaMirrorClient,
+        mint: &Pubkey,
+        _decimals: u8,
+    ) -> Result<Option<f64>, lib::Error> {
+        let prices = HashMap::from([(lib::SOL_ADDRESS, SOL_PRICE), (BONK, BONK_PRICE)]);
+        Ok(prices.get(mint.to_string().as_str()).copied())
+    }
+}
+
+static PRICE_CHAIN: Once = Once::new();
+
+/// Makes every price lookup of the test binary go through `FixedPriceSource`
+pub fn use_fixed_prices() {
+    PRICE_CHAIN.call_once(|| set_price_chain(PriceChain::new().with_source(FixedPriceSource)));
+}
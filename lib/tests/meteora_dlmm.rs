@@ -0,0 +1,80 @@
+mod common;
+
+use lib::{
+    balances::dapps::{meteora::MeteoraDlmmAdapter, DappAdapter},
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    SOL_ADDRESS, USDC_ADDRESS,
+};
+use serde_json::{json, Value};
+
+use common::{client, fixture, owner};
+
+const PAIR: &str = "BGm1tav58oGcsQJehL9WXBFXF7D27vZsKefj4xJKD5Y";
+/// Bin array holding bins -4830 to -4761, where the position starts
+const LOWER_BIN_ARRAY: &str = "5FVmM1vm6zodGb5ijASD5pth7mmeVLSw1F7bDoBbgPbJ";
+
+fn token_supply(decimals: u8) -> Value {
+    json!({
+        "context": { "apiVersion": "2.0.15", "slot": 300000000 },
+        "value": { "amount": "0", "decimals": decimals, "uiAmount": 0.0, "uiAmountString": "0" },
+    })
+}
+
+fn transport() -> FixtureTransport {
+    FixtureTransport::new()
+        .with_result(
+            JsonRpcMethod::GetProgramAccounts,
+            fixture("meteora_dlmm_positions"),
+        )
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            PAIR,
+            fixture("meteora_dlmm_pair"),
+        )
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            LOWER_BIN_ARRAY,
+            fixture("meteora_dlmm_bin_arrays"),
+        )
+        .with_result_for(JsonRpcMethod::GetDecimals, SOL_ADDRESS, token_supply(9))
+        .with_result_for(JsonRpcMethod::GetDecimals, USDC_ADDRESS, token_supply(6))
+}
+
+#[rocket::async_test]
+async fn decodes_the_bin_shares_and_fees_of_a_position() {
+    let client = client(transport());
+    let adapter = MeteoraDlmmAdapter;
+
+    let positions = adapter.discover(&client, &owner()).await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert!(adapter.matches(&positions[0]));
+
+    let snapshot = adapter.fetch(&client, None, &positions[0]).await.unwrap();
+    assert_eq!(snapshot.mint, None);
+    assert_eq!(snapshot.pool_id.to_string(), PAIR);
+    // Base factor of 10,000 on bins 4 basis points apart
+    assert_eq!(snapshot.fee_tier, "0.04%");
+
+    // A quarter of bins -4770 to -4740: the 26 below the active bin hold 40 USDC each,
+    // the active one 0.1 SOL and 20 USDC and the 4 above it 0.3 SOL each
+    assert_eq!(snapshot.token_a.mint.to_string(), SOL_ADDRESS);
+    assert_eq!(snapshot.token_a.decimals, 9);
+    assert_eq!(snapshot.token_a.amount, 25_000_000.0 + 4.0 * 75_000_000.0);
+    assert_eq!(snapshot.token_b.mint.to_string(), USDC_ADDRESS);
+    assert_eq!(snapshot.token_b.decimals, 6);
+    assert_eq!(snapshot.token_b.amount, 26.0 * 10_000_000.0 + 5_000_000.0);
+
+    // The range ends where bin -4740 does
+    let range = snapshot.price_range.unwrap();
+    assert!((range.lower - 0.148433460).abs() < 1e-9);
+    assert!((range.upper - 0.150285121).abs() < 1e-9);
+    assert!((range.current - 0.149984911).abs() < 1e-9);
+
+    // 1,000,000 shares in the active bin, whose fees grew by 12 lamports and 30 USDC base
+    // units per share since the position settled them, plus 1.5 USDC pending in another bin
+    assert_eq!(snapshot.fees.len(), 2);
+    assert_eq!(snapshot.fees[0].mint.to_string(), SOL_ADDRESS);
+    assert_eq!(snapshot.fees[0].amount, 12_000_000.0);
+    assert_eq!(snapshot.fees[1].mint.to_string(), USDC_ADDRESS);
+    assert_eq!(snapshot.fees[1].amount, 30_000_000.0 + 1_500_000.0);
+}
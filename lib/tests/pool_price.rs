@@ -0,0 +1,50 @@
+mod common;
+
+use lib::{
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    price::{pool::PoolPriceSource, PriceSource},
+};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+use common::client;
+
+#[rocket::async_test]
+async fn skips_tokens_whose_scans_failed_recently() {
+    let failing = client(FixtureTransport::new().with_error(
+        JsonRpcMethod::GetProgramAccounts,
+        -32010,
+        "excluded from account secondary indexes",
+    ));
+    let mint = Pubkey::new_unique();
+
+    let prices = PoolPriceSource.get_prices(&failing, &[(mint, 6)]).await;
+    assert!(prices.prices.is_empty());
+    assert!(prices.failed.contains(&mint));
+
+    // The scans would succeed now, but the token is still skipped
+    let working =
+        client(FixtureTransport::new().with_result(JsonRpcMethod::GetProgramAccounts, json!([])));
+    let prices = PoolPriceSource.get_prices(&working, &[(mint, 6)]).await;
+    assert!(prices.failed.contains(&mint));
+
+    let other_mint = Pubkey::new_unique();
+    let prices = PoolPriceSource
+        .get_prices(&working, &[(other_mint, 6)])
+        .await;
+    assert!(prices.prices.is_empty());
+    assert!(prices.failed.is_empty());
+}
+
+#[rocket::async_test]
+async fn looks_up_a_few_tokens_per_request() {
+    let client =
+        client(FixtureTransport::new().with_result(JsonRpcMethod::GetProgramAccounts, json!([])));
+    let mints: Vec<(Pubkey, u8)> = (0..25).map(|_| (Pubkey::new_unique(), 6)).collect();
+
+    let prices = PoolPriceSource.get_prices(&client, &mints).await;
+
+    // Tokens without a pool are unknown, the ones past the limit count as failed
+    assert!(prices.prices.is_empty());
+    assert_eq!(prices.failed.len(), 15);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use lib::{
+    balances::{
+        accounts::types::ParsedAta,
+        dapps::{raydium_amm::AmmV4Adapter, DappAdapter},
+    },
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    types::FormattedAmount,
+    SOL_ADDRESS, USDC_ADDRESS,
+};
+
+use common::{client, fixture};
+
+const LP_MINT: &str = "8HoQnePLqPj4M7PUDzfw8e3Ymdwgc7NLGnaTUapubyvu";
+const POOL: &str = "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2";
+const COIN_VAULT: &str = "DQyrAcCrDXQ7NeoqGgDCZwBvWDcYmFCjSb9JtteuvPpz";
+
+fn transport() -> FixtureTransport {
+    FixtureTransport::new()
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            LP_MINT,
+            fixture("raydium_amm_lp_mint"),
+        )
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            COIN_VAULT,
+            fixture("raydium_amm_vaults"),
+        )
+        .with_result(
+            JsonRpcMethod::GetProgramAccounts,
+            fixture("raydium_amm_pool"),
+        )
+}
+
+/// 4 of the 1024 LP tokens in supply, a 1/256 share of the pool
+fn lp_token() -> ParsedAta {
+    ParsedAta {
+        mint: LP_MINT.to_string(),
+        decimals: 9,
+        balance: FormattedAmount {
+            amount: "4000000000".to_string(),
+            formatted: 4.0,
+        },
+        ..Default::default()
+    }
+}
+
+#[rocket::async_test]
+async fn values_the_lp_share_of_the_pool_reserves() {
+    let client = client(transport());
+    let adapter = AmmV4Adapter;
+    let token = lp_token();
+
+    let mint = adapter.detect(&token).unwrap();
+    let mint = client.get_multiple_accounts(&[mint]).await.unwrap()[0]
+        .take()
+        .unwrap();
+    assert!(adapter.matches(&mint));
+
+    let snapshot = adapter.fetch(&client, Some(&token), &mint).await.unwrap();
+    assert_eq!(snapshot.mint.as_deref(), Some(LP_MINT));
+    assert_eq!(snapshot.pool_id.to_string(), POOL);
+    assert_eq!(snapshot.fee_tier, "0.25%");
+    assert!(snapshot.price_range.is_none());
+
+    // The vaults hold 50,000 SOL and 7,500,000 USDC, minus 12 SOL and 1,800 USDC of
+    // protocol fees that weren't taken out yet
+    assert_eq!(snapshot.token_a.mint.to_string(), SOL_ADDRESS);
+    assert_eq!(snapshot.token_a.decimals, 9);
+    assert_eq!(snapshot.token_a.amount, 49_988_000_000_000.0 / 256.0);
+    assert_eq!(snapshot.token_b.mint.to_string(), USDC_ADDRESS);
+    assert_eq!(snapshot.token_b.decimals, 6);
+    assert_eq!(snapshot.token_b.amount, 7_498_200_000_000.0 / 256.0);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use lib::{
+    balances::dapps::{
+        raydium::{get_position_address, RaydiumAdapter},
+        DappAdapter,
+    },
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    SOL_ADDRESS, USDC_ADDRESS,
+};
+use serde_json::json;
+
+use common::{client, fixture};
+
+const NFT_MINT: &str = "8dv1ghXjxc6ZcuJXgUxmeRtbgYAkMghNhd3uYAPNaBKb";
+const POOL: &str = "3ucNos4NbumPLZNWztqGHNFFgkHeRMBQAVemeeomsUxv";
+const AMM_CONFIG: &str = "9iFER3bpjf1PTTCQCfTRu17EJgvsxo9pVyA9QWwEuX4x";
+const RAY: &str = "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R";
+/// Tick array holding the lower tick of the position
+const LOWER_TICK_ARRAY: &str = "ASTk55REVposu6dLyEWQ129146MySf8A7qHzdADq9hXR";
+
+fn transport() -> FixtureTransport {
+    let position = get_position_address(NFT_MINT).unwrap().to_string();
+
+    FixtureTransport::new()
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            &position,
+            fixture("raydium_clmm_position"),
+        )
+        .with_result_containing(
+            JsonRpcMethod::GetMultipleAccounts,
+            LOWER_TICK_ARRAY,
+            fixture("raydium_clmm_tick_arrays"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetAccountInfo,
+            POOL,
+            fixture("raydium_clmm_pool"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetAccountInfo,
+            AMM_CONFIG,
+            fixture("raydium_clmm_amm_config"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetDecimals,
+            RAY,
+            json!({
+                "context": { "apiVersion": "2.0.15", "slot": 300000000 },
+                "value": { "amount": "555000000000000", "decimals": 6, "uiAmount": 555000000.0, "uiAmountString": "555000000" },
+            }),
+        )
+}
+
+#[rocket::async_test]
+async fn decodes_amounts_fees_and_rewards_of_a_position() {
+    let client = client(transport());
+    let adapter = RaydiumAdapter;
+
+    let address = get_position_address(NFT_MINT).unwrap();
+    let position = client.get_multiple_accounts(&[address]).await.unwrap()[0]
+        .take()
+        .unwrap();
+    assert!(adapter.matches(&position));
+
+    let snapshot = adapter.fetch(&client, None, &position).await.unwrap();
+    assert_eq!(snapshot.pool_id.to_string(), POOL);
+    assert_eq!(snapshot.fee_tier, "0.04%");
+
+    // 1e12 of liquidity between ticks -19470 and -18450, with the pool at a price of 0.15
+    assert_eq!(snapshot.token_a.mint.to_string(), SOL_ADDRESS);
+    assert_eq!(snapshot.token_a.decimals, 9);
+    assert!((snapshot.token_a.amount - 66_533_453_813.0).abs() <= 1.0);
+    assert_eq!(snapshot.token_b.mint.to_string(), USDC_ADDRESS);
+    assert_eq!(snapshot.token_b.decimals, 6);
+    assert!((snapshot.token_b.amount - 9_521_381_354.0).abs() <= 1.0);
+
+    let range = snapshot.price_range.unwrap();
+    assert!((range.lower - 0.142715426).abs() < 1e-9);
+    assert!((range.upper - 0.158039898).abs() < 1e-9);
+    assert!((range.current - 0.15).abs() < 1e-12);
+
+    // Growth inside is the global one minus the outside growth of both ticks:
+    // 30e-5 - 12e-5 - 3e-5 for SOL, 10e-5 of which was settled at the last update
+    assert_eq!(snapshot.fees.len(), 2);
+    assert_eq!(snapshot.fees[0].mint.to_string(), SOL_ADDRESS);
+    assert_eq!(snapshot.fees[0].amount, 1_000_000.0 + 50_000_000.0);
+    assert_eq!(snapshot.fees[1].mint.to_string(), USDC_ADDRESS);
+    assert_eq!(snapshot.fees[1].amount, 15_000_000.0);
+
+    // The farm ended, so only the growth stored in the pool counts
+    assert_eq!(snapshot.rewards.len(), 1);
+    assert_eq!(snapshot.rewards[0].mint.to_string(), RAY);
+    assert_eq!(snapshot.rewards[0].decimals, 6);
+    assert_eq!(snapshot.rewards[0].amount, 250_000.0 + 1_000_000.0);
+}
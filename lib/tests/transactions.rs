@@ -0,0 +1,84 @@
+mod common;
+
+use lib::{
+    client::{transport::FixtureTransport, JsonRpcMethod},
+    transactions::get_parsed_transactions,
+    Page, SOL_ADDRESS,
+};
+
+use common::{client, fixture, owner, BONK, SIGNATURE_RECEIVE, SIGNATURE_SEND};
+
+fn transport() -> FixtureTransport {
+    FixtureTransport::new()
+        .with_result(
+            JsonRpcMethod::GetSignaturesForAddress,
+            fixture("signatures"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetTransaction,
+            SIGNATURE_RECEIVE,
+            fixture("transaction_receive"),
+        )
+        .with_result_for(
+            JsonRpcMethod::GetTransaction,
+            SIGNATURE_SEND,
+            fixture("transaction_send"),
+        )
+}
+
+#[rocket::async_test]
+async fn parses_transactions_oldest_first() {
+    let client = client(transport());
+
+    let response = get_parsed_transactions(&client, &owner(), None)
+        .await
+        .unwrap();
+    assert_eq!(response.count, 2);
+    assert_eq!(response.transactions.len(), 2);
+
+    let receive = &response.transactions[0];
+    assert_eq!(receive.signatures, vec![SIGNATURE_RECEIVE.to_string()]);
+    assert_eq!(receive.block_time, 1725000000);
+    assert_eq!(receive.parsed_instructions, vec!["Transfer".to_string()]);
+    assert_eq!(receive.balances[SOL_ADDRESS].pre.formatted, 1.0);
+    assert_eq!(receive.balances[SOL_ADDRESS].post.formatted, 3.0);
+    assert_eq!(receive.balances[BONK].post.amount, "150000000000");
+
+    let send = &response.transactions[1];
+    assert_eq!(
+        send.parsed_instructions,
+        vec!["TransferChecked".to_string()]
+    );
+    assert_eq!(send.balances[BONK].pre.formatted, 1_500_000.0);
+    assert_eq!(send.balances[BONK].post.formatted, 1_000_000.0);
+    assert_eq!(send.balances[SOL_ADDRESS].post.amount, "2999995000");
+}
+
+#[rocket::async_test]
+async fn only_fetches_the_requested_page() {
+    let client = client(transport());
+
+    // Signatures come newest first, so the first one is the send
+    let page = Page {
+        start_idx: 0,
+        end_idx: 1,
+    };
+    let response = get_parsed_transactions(&client, &owner(), Some(page))
+        .await
+        .unwrap();
+    assert_eq!(response.count, 2);
+    assert_eq!(response.transactions.len(), 1);
+    assert_eq!(
+        response.transactions[0].signatures,
+        vec![SIGNATURE_SEND.to_string()]
+    );
+
+    let past_the_end = Page {
+        start_idx: 5,
+        end_idx: 10,
+    };
+    let response = get_parsed_transactions(&client, &owner(), Some(past_the_end))
+        .await
+        .unwrap();
+    assert!(response.transactions.is_empty());
+}
@@ -0,0 +1,154 @@
+use lib::{portfolio::MAX_PORTFOLIO_WALLETS, Error};
+use rocket::{
+    http::{Header, Status},
+    response::{self, Responder},
+    serde::json::Json,
+    Request, Response,
+};
+use serde::Serialize;
+
+/// Error returned by every route as `{ "error": { "code", "message", "retryAfter" } }`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub code: &'static str,
+    pub message: String,
+    /// Seconds the client should wait before retrying
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: ErrorDetails,
+}
+
+#[derive(Serialize)]
+struct ErrorDetails {
+    code: &'static str,
+    message: String,
+    #[serde(rename = "retryAfter", skip_serializing_if = "Option::is_none")]
+    retry_after: Option<u64>,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            code,
+            message: message.to_string(),
+            retry_after: None,
+        }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        let api_error = match &err {
+            Error::InvalidAddress => ApiError::new(
+                Status::BadRequest,
+                "INVALID_ADDRESS",
+                "The address is not a valid public key",
+            ),
+            Error::InvalidIndex => ApiError::new(
+                Status::BadRequest,
+                "INVALID_INDEX",
+                "The index must be a range like 0-100",
+            ),
+            Error::InvalidTimeframe => ApiError::new(
+                Status::BadRequest,
+                "INVALID_TIMEFRAME",
+                "The timeframe must be an amount of hours or days like 24h or 30d",
+            ),
+            Error::InvalidValuation => ApiError::new(
+                Status::BadRequest,
+                "INVALID_VALUATION",
+                "The valuation must be either market or liquidation",
+            ),
+            Error::TooManyAddresses => ApiError::new(
+                Status::BadRequest,
+                "TOO_MANY_ADDRESSES",
+                &format!(
+                    "A portfolio can span up to {} addresses",
+                    MAX_PORTFOLIO_WALLETS
+                ),
+            ),
+            Error::TooManyRequests { retry_after, .. } => ApiError {
+                retry_after: retry_after.map(|d| d.as_secs()),
+                ..ApiError::new(
+                    Status::TooManyRequests,
+                    "RATE_LIMITED",
+                    "The upstream RPC is rate limiting requests, try again later",
+                )
+            },
+            Error::RpcError { code, message, .. } => ApiError::new(
+                Status::BadGateway,
+                "UPSTREAM_ERROR",
+                &format!("The upstream RPC returned an error ({}): {}", code, message),
+            ),
+            Error::FetchError(_) => ApiError::new(
+                Status::BadGateway,
+                "UPSTREAM_UNAVAILABLE",
+                "The upstream RPC could not be reached",
+            ),
+            Error::ParseError(_) => ApiError::new(
+                Status::BadGateway,
+                "UPSTREAM_INVALID_RESPONSE",
+                "The upstream RPC returned an unexpected response",
+            ),
+        };
+
+        // The full error can carry urls with api keys, so it only goes to the logs
+        if api_error.status.code >= 500 {
+            eprintln!("{}", err);
+        }
+
+        api_error
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let body = ErrorBody {
+            error: ErrorDetails {
+                code: self.code,
+                message: self.message,
+                retry_after: self.retry_after,
+            },
+        };
+
+        let mut response = Response::build_from(Json(body).respond_to(request)?);
+        response.status(self.status);
+        if let Some(retry_after) = self.retry_after {
+            response.header(Header::new("Retry-After", retry_after.to_string()));
+        }
+
+        response.ok()
+    }
+}
+
+#[catch(404)]
+pub fn not_found() -> ApiError {
+    ApiError::new(
+        Status::NotFound,
+        "NOT_FOUND",
+        "The requested route does not exist",
+    )
+}
+
+#[catch(422)]
+pub fn unprocessable_entity() -> ApiError {
+    ApiError::new(
+        Status::UnprocessableEntity,
+        "UNPROCESSABLE_ENTITY",
+        "The request parameters could not be parsed",
+    )
+}
+
+#[catch(500)]
+pub fn internal_error() -> ApiError {
+    ApiError::new(
+        Status::InternalServerError,
+        "INTERNAL_ERROR",
+        "Something went wrong on our side",
+    )
+}
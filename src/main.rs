@@ -1,11 +1,17 @@
 use dotenv::dotenv;
 #[macro_use]
 extern crate rocket;
+use lib::balances::dapps::registry::DappRegistry;
+use lib::client::{limiter::RateLimit, SolanaMirrorClient};
+use lib::coingecko::CoingeckoClient;
+use lib::price::set_price_chain;
+use lib::utils::{get_price_sources, get_rate_limit, get_rpc_endpoints, get_rpc_strategy};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::http::Header;
 use rocket::http::Method;
 use rocket::{Request, Response};
 
+mod error;
 mod routes;
 
 pub struct CORS;
@@ -20,14 +26,36 @@ fn rocket() -> _ {
         ..Default::default()
     };
 
-    rocket::custom(config).attach(CORS).mount(
-        "/",
-        routes![
-            routes::accounts_handler,
-            routes::transactions_handler,
-            routes::chart_handler
-        ],
-    )
+    // Shared across requests so the endpoint health survives between them
+    let client = SolanaMirrorClient::from_endpoints(get_rpc_endpoints(), get_rpc_strategy())
+        .with_rate_limit(get_rate_limit("RPC", RateLimit::default()));
+
+    set_price_chain(get_price_sources());
+
+    rocket::custom(config)
+        .attach(CORS)
+        .manage(client)
+        .manage(CoingeckoClient::new())
+        .manage(DappRegistry::default())
+        .register(
+            "/",
+            catchers![
+                error::not_found,
+                error::unprocessable_entity,
+                error::internal_error
+            ],
+        )
+        .mount(
+            "/",
+            routes![
+                routes::accounts_handler,
+                routes::transactions_handler,
+                routes::chart_handler,
+                routes::nfts_handler,
+                routes::portfolio_balances_handler,
+                routes::portfolio_chart_handler
+            ],
+        )
 }
 
 #[rocket::async_trait]
@@ -40,7 +68,7 @@ impl Fairing for CORS {
     }
 
     async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        let allowed_origins = vec!["http://localhost:3000", "https://solanamirror.xyz"];
+        let allowed_origins = ["http://localhost:3000", "https://solanamirror.xyz"];
         if let Some(origin) = _request.headers().get_one("Origin") {
             if allowed_origins.contains(&origin) {
                 response.set_header(Header::new("Access-Control-Allow-Origin", origin));
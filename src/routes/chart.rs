@@ -3,79 +3,70 @@ use std::str::FromStr;
 use lib::{
     chart::{
         get_chart_data,
-        types::{ChartResponse, MinimalChartData},
+        types::{ChartDataWithPrice, ChartResponse, MinimalChartData},
         Timeframe,
     },
     client::SolanaMirrorClient,
     coingecko::CoingeckoClient,
-    utils::get_rpc,
-    Error::{FetchError, InvalidAddress, InvalidTimeframe, ParseError, TooManyRequests},
+    Error,
 };
-use reqwest::Client;
-use rocket::{http::Status, serde::json::Json};
+use rocket::{serde::json::Json, State};
 use spl_token::solana_program::pubkey::Pubkey;
 
+use crate::error::ApiError;
+
 #[get("/chart/<address>/<timeframe>?<detailed>")]
 pub async fn chart_handler(
+    client: &State<SolanaMirrorClient>,
+    coingecko: &State<CoingeckoClient>,
     address: &str,
     timeframe: &str,
     detailed: Option<bool>,
-) -> Result<Json<ChartResponse>, Status> {
+) -> Result<Json<ChartResponse>, ApiError> {
+    let (parsed_timeframe, range) = parse_timeframe(timeframe)?;
+
+    let pubkey = Pubkey::from_str(address).map_err(|_| Error::InvalidAddress)?;
+
+    let data = get_chart_data(client, coingecko, &pubkey, parsed_timeframe, range).await?;
+
+    Ok(Json(get_chart_response(data, detailed.unwrap_or(false))))
+}
+
+/// Splits a timeframe like `24h` or `30d` into its unit and its range
+pub(crate) fn parse_timeframe(timeframe: &str) -> Result<(Timeframe, u8), Error> {
     // Gets the last character of the timeframe string (either "d" or "h")
     let timeframe_str = &timeframe[timeframe.len() - 1..];
     let parsed_timeframe = match Timeframe::new(timeframe_str) {
         Some(parsed_timeframe) => parsed_timeframe,
-        None => return Err(Status::BadRequest),
+        None => return Err(Error::InvalidTimeframe),
     };
 
     // Gets the rest of the timeframe string (the amount of hours/days)
     let range = match timeframe[..timeframe.len() - 1].parse::<u8>() {
         Ok(range) => {
-            if timeframe_str.to_lowercase() == "h" && range > 24 * 90 {
-                return Err(Status::BadRequest);
+            if timeframe_str.to_lowercase() == "h" && u16::from(range) > 24 * 90 {
+                return Err(Error::InvalidTimeframe);
             }
             range
         }
-        Err(_) => return Err(Status::BadRequest),
+        Err(_) => return Err(Error::InvalidTimeframe),
     };
 
-    let pubkey = match Pubkey::from_str(address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Err(Status::BadRequest),
-    };
-
-    let reqwest = Client::new();
-    let coingecko = CoingeckoClient::from_client(&reqwest);
-    let client = SolanaMirrorClient::from_client(&reqwest, get_rpc());
-
-    let chart_data = get_chart_data(&client, &coingecko, &pubkey, parsed_timeframe, range).await;
+    Ok((parsed_timeframe, range))
+}
 
-    match chart_data {
-        Ok(data) => {
-            if detailed.unwrap_or(false) {
-                Ok(Json(ChartResponse::Detailed(data)))
-            } else {
-                let minimal_chart_data: Vec<MinimalChartData> = data
-                    .iter()
-                    .map(|x| MinimalChartData {
-                        timestamp: x.timestamp,
-                        usd_value: x.usd_value,
-                    })
-                    .collect();
+pub(crate) fn get_chart_response(data: Vec<ChartDataWithPrice>, detailed: bool) -> ChartResponse {
+    if detailed {
+        ChartResponse::Detailed(data)
+    } else {
+        let minimal_chart_data: Vec<MinimalChartData> = data
+            .iter()
+            .map(|x| MinimalChartData {
+                timestamp: x.timestamp,
+                usd_value: x.usd_value,
+            })
+            .collect();
 
-                Ok(Json(ChartResponse::Minimal(minimal_chart_data)))
-            }
-        }
-        Err(err) => {
-            let status_code = match err {
-                ParseError => Status::InternalServerError,
-                TooManyRequests => Status::TooManyRequests,
-                FetchError => Status::InternalServerError,
-                InvalidAddress => Status::BadRequest,
-                InvalidTimeframe => Status::BadRequest,
-                _ => Status::InternalServerError,
-            };
-            Err(status_code)
-        }
+        ChartResponse::Minimal(minimal_chart_data)
     }
 }
@@ -1,7 +1,11 @@
 pub mod balances;
 pub mod chart;
+pub mod nfts;
+pub mod portfolio;
 pub mod transactions;
 
 pub use balances::accounts_handler;
 pub use chart::chart_handler;
+pub use nfts::nfts_handler;
+pub use portfolio::{portfolio_balances_handler, portfolio_chart_handler};
 pub use transactions::transactions_handler;
@@ -0,0 +1,21 @@
+use lib::client::SolanaMirrorClient;
+use lib::nfts::get_nfts;
+use lib::nfts::types::NftsResponse;
+use lib::Error;
+use rocket::{serde::json::Json, State};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::error::ApiError;
+
+#[get("/nfts/<address>")]
+pub async fn nfts_handler(
+    client: &State<SolanaMirrorClient>,
+    address: &str,
+) -> Result<Json<NftsResponse>, ApiError> {
+    let pubkey = Pubkey::from_str(address).map_err(|_| Error::InvalidAddress)?;
+
+    let nfts = get_nfts(client, &pubkey).await?;
+
+    Ok(Json(nfts))
+}
@@ -0,0 +1,51 @@
+use lib::{
+    balances::dapps::registry::DappRegistry,
+    chart::{get_portfolio_chart_data, types::ChartResponse},
+    client::SolanaMirrorClient,
+    coingecko::CoingeckoClient,
+    portfolio::{get_portfolio, parse_addresses, types::PortfolioResponse},
+};
+use rocket::{serde::json::Json, State};
+
+use crate::error::ApiError;
+
+use super::chart::{get_chart_response, parse_timeframe};
+
+/// Balances of several wallets merged, `addresses` is a comma separated list
+#[get("/portfolio/balances?<addresses>&<showApps>&<hideSpam>&<minUsd>")]
+pub async fn portfolio_balances_handler(
+    client: &State<SolanaMirrorClient>,
+    registry: &State<DappRegistry>,
+    addresses: &str,
+    #[allow(non_snake_case)] showApps: Option<bool>,
+    #[allow(non_snake_case)] hideSpam: Option<bool>,
+    #[allow(non_snake_case)] minUsd: Option<f64>,
+) -> Result<Json<PortfolioResponse>, ApiError> {
+    let show_apps = showApps.unwrap_or(true);
+    let hide_spam = hideSpam.unwrap_or(false);
+
+    let pubkeys = parse_addresses(addresses)?;
+
+    let portfolio = get_portfolio(client, registry, &pubkeys, show_apps, hide_spam, minUsd).await?;
+
+    Ok(Json(portfolio))
+}
+
+/// Chart of several wallets on a common timeline, `addresses` is a comma separated list
+#[get("/portfolio/chart/<timeframe>?<addresses>&<detailed>")]
+pub async fn portfolio_chart_handler(
+    client: &State<SolanaMirrorClient>,
+    coingecko: &State<CoingeckoClient>,
+    timeframe: &str,
+    addresses: &str,
+    detailed: Option<bool>,
+) -> Result<Json<ChartResponse>, ApiError> {
+    let (parsed_timeframe, range) = parse_timeframe(timeframe)?;
+
+    let pubkeys = parse_addresses(addresses)?;
+
+    let data =
+        get_portfolio_chart_data(client, coingecko, &pubkeys, parsed_timeframe, range).await?;
+
+    Ok(Json(get_chart_response(data, detailed.unwrap_or(false))))
+}
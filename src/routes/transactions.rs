@@ -1,40 +1,24 @@
 use lib::client::SolanaMirrorClient;
 use lib::transactions::get_parsed_transactions;
 use lib::transactions::types::TransactionResponse;
-use lib::utils::{get_rpc, parse_page};
-use lib::Error::{InvalidAddress, TooManyRequests};
-use rocket::{http::Status, serde::json::Json};
+use lib::utils::parse_page;
+use lib::Error;
+use rocket::{serde::json::Json, State};
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
+use crate::error::ApiError;
+
 #[get("/transactions/<address>?<index>")]
 pub async fn transactions_handler(
+    client: &State<SolanaMirrorClient>,
     address: &str,
     index: Option<&str>,
-) -> Result<Json<TransactionResponse>, Status> {
-    let client = SolanaMirrorClient::new(get_rpc());
-
-    let pubkey = match Pubkey::from_str(address) {
-        Ok(pubkey) => pubkey,
-        Err(_) => return Err(Status::BadRequest),
-    };
-
-    let page = match parse_page(index) {
-        Ok(p) => p,
-        Err(_) => return Err(Status::BadRequest),
-    };
+) -> Result<Json<TransactionResponse>, ApiError> {
+    let pubkey = Pubkey::from_str(address).map_err(|_| Error::InvalidAddress)?;
+    let page = parse_page(index)?;
 
-    let parsed_transactions = get_parsed_transactions(&client, &pubkey, page).await;
+    let parsed_transactions = get_parsed_transactions(client, &pubkey, page).await?;
 
-    match parsed_transactions {
-        Ok(txs) => Ok(Json(txs)),
-        Err(err) => {
-            let status_code = match err {
-                InvalidAddress => Status::BadRequest,
-                TooManyRequests => Status::TooManyRequests,
-                _ => Status::InternalServerError,
-            };
-            Err(status_code)
-        }
-    }
+    Ok(Json(parsed_transactions))
 }